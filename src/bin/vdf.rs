@@ -0,0 +1,410 @@
+//! The `vdf` command-line tool, for working with VDF files from the shell
+
+use std::borrow::Cow;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use lexopt::{Arg, ValueExt};
+use vdf_serde::{Document, Encoding, Event, Map, Patch, PatchOp, Query, Reader, Value, Writer};
+
+const USAGE: &str = "\
+usage: vdf <command> [options] [file...]
+
+Files can be - or left out to use stdin.
+
+commands:
+    fmt [--write | --check] [file...]   reformat files, keeping comments, to stdout or in place with --write
+    check [file...]                     report syntax errors with their positions
+    get [-i] [--paths] <path> [file]    print the values at a path like AppState/*/manifest
+    set [-i] [-w] <path> <value> [file] set the values at a path, or add it and any groups it's missing
+    to-json [file]                      convert VDF to JSON, keeping conditions
+    from-json [file]                    convert JSON to VDF
+    diff <old> <new>                    show what changed between two files
+
+exit status is 0 on success, 1 if check finds errors, fmt --check finds unformatted files,
+get finds nothing, or diff finds differences, and 2 if something goes wrong.";
+
+/// Whatever went wrong, already formatted for the user
+type Result<T> = std::result::Result<T, String>;
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(err) => {
+            eprintln!("vdf: {}", err);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Returns whether the command succeeded, in the sense that decides the exit status
+fn run() -> Result<bool> {
+    let mut parser = lexopt::Parser::from_env();
+    let command = match parser.next().map_err(|err| err.to_string())? {
+        Some(Arg::Value(command)) => command.string().map_err(|err| err.to_string())?,
+        Some(Arg::Short('h')) | Some(Arg::Long("help")) => {
+            println!("{}", USAGE);
+            return Ok(true);
+        }
+        Some(arg) => return Err(arg.unexpected().to_string()),
+        None => return Err(format!("no command given\n\n{}", USAGE)),
+    };
+    let args = Args::parse(&mut parser).map_err(|err| err.to_string())?;
+    match command.as_str() {
+        "fmt" => fmt(args),
+        "check" => check(args),
+        "get" => get(args),
+        "set" => set(args),
+        "to-json" => to_json(args),
+        "from-json" => from_json(args),
+        "diff" => diff(args),
+        _ => Err(format!("unknown command {:?}\n\n{}", command, USAGE)),
+    }
+}
+
+/// Everything after the command, since the commands share most of their options
+#[derive(Default)]
+struct Args {
+    write: bool,
+    check: bool,
+    case_insensitive: bool,
+    paths: bool,
+    positional: Vec<String>,
+}
+
+impl Args {
+    fn parse(parser: &mut lexopt::Parser) -> std::result::Result<Self, lexopt::Error> {
+        let mut args = Args::default();
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Arg::Short('w') | Arg::Long("write") => args.write = true,
+                Arg::Long("check") => args.check = true,
+                Arg::Short('i') | Arg::Long("ignore-case") => args.case_insensitive = true,
+                Arg::Long("paths") => args.paths = true,
+                Arg::Value(value) => args.positional.push(value.string()?),
+                _ => return Err(arg.unexpected()),
+            }
+        }
+        Ok(args)
+    }
+
+    /// Takes the next positional argument, which has to be there
+    fn required(&mut self, name: &str) -> Result<String> {
+        if self.positional.is_empty() {
+            return Err(format!("missing <{}>", name));
+        }
+        Ok(self.positional.remove(0))
+    }
+
+    /// Takes the rest of the positional arguments as files, with stdin if there aren't any
+    fn files(&mut self) -> Vec<String> {
+        match std::mem::take(&mut self.positional) {
+            files if files.is_empty() => vec!["-".to_string()],
+            files => files,
+        }
+    }
+
+    /// Takes the one file argument, with stdin if there isn't one
+    fn file(&mut self) -> Result<String> {
+        let mut files = self.files();
+        if files.len() > 1 {
+            return Err(format!("unexpected argument {:?}", files[1]));
+        }
+        Ok(files.remove(0))
+    }
+}
+
+/// A file's text, and how it was encoded, so it can be written back the same way
+struct Source {
+    text: String,
+    encoding: Encoding,
+    bom: bool,
+}
+
+impl Source {
+    /// Encodes `text` the way this file was, with a byte order mark only if it had one
+    fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        let encoded = self.encoding.encode(text).map_err(|err| err.to_string())?;
+        let bom = self.encoding.bom();
+        let body = encoded.strip_prefix(bom).unwrap_or(&encoded);
+        Ok([if self.bom { bom } else { &[] }, body].concat())
+    }
+}
+
+/// Reads a file in whichever encoding it turns out to be in, like `from_bytes` does
+fn read(file: &str) -> Result<Source> {
+    let mut bytes = Vec::new();
+    let read = if file == "-" {
+        io::stdin().read_to_end(&mut bytes).map(|_| ())
+    } else {
+        fs::read(file).map(|contents| bytes = contents)
+    };
+    read.map_err(|err| format!("{}: {}", file, err))?;
+    let encoding = Encoding::detect(&bytes);
+    let text = encoding.decode(&bytes).map_err(|err| format!("{}: {}", file, err))?;
+    let bom = !encoding.bom().is_empty() && bytes.starts_with(encoding.bom());
+    Ok(Source { text, encoding, bom })
+}
+
+fn write(file: &str, text: &str) -> Result<()> {
+    write_bytes(file, text.as_bytes())
+}
+
+fn write_bytes(file: &str, bytes: &[u8]) -> Result<()> {
+    if file == "-" {
+        io::stdout().write_all(bytes)
+    } else {
+        fs::write(file, bytes)
+    }
+    .map_err(|err| format!("{}: {}", file, err))
+}
+
+/// Parses a document, with the position of any syntax error in the error
+fn parse(file: &str, text: &str) -> Result<Value> {
+    let mut reader = Reader::new(text);
+    while let Some(item) = reader.next() {
+        if let Err(err) = item {
+            return Err(format!("{}:{}: {}", file, reader.position(), err));
+        }
+    }
+    text.parse().map_err(|err| format!("{}: {}", file, err))
+}
+
+fn read_value(file: &str) -> Result<Value> {
+    parse(file, &read(file)?.text)
+}
+
+/// Reformats a document one event at a time, so its comments, directives, and conditions stay where they are
+fn reformatted(file: &str, text: &str) -> Result<String> {
+    parse(file, text)?;
+    let mut writer = Writer::new(Vec::new());
+    let mut key = None;
+    let mut directive = None;
+    let mut condition = None;
+    // a pair waits to be written until it's known whether a condition comes after it
+    let mut pair: Option<(Cow<str>, Cow<str>)> = None;
+    let mut last_end = None;
+    for item in Reader::new(text) {
+        let (event, span) = item.map_err(|err| format!("{}: {}", file, err))?;
+        if let Event::Conditional(text) = event {
+            match (pair.take(), &key) {
+                (Some((key, value)), _) => writer.write_conditional_pair(&key, &value, &text).map_err(|err| err.to_string())?,
+                (None, Some(_)) => condition = Some(text),
+                (None, None) => return Err(format!("{}: [{}] isn't after a pair or a group's key", file, text)),
+            }
+            last_end = Some(span.end);
+            continue;
+        }
+        if let Some((key, value)) = pair.take() {
+            writer.write_pair(&key, &value).map_err(|err| err.to_string())?;
+        }
+        let written = match event {
+            Event::Key(name) => {
+                key = Some(name);
+                Ok(())
+            }
+            Event::Directive(name) => {
+                directive = Some(name);
+                Ok(())
+            }
+            Event::Value(value) => match (key.take(), directive.take()) {
+                (Some(key), _) => {
+                    pair = Some((key, value));
+                    Ok(())
+                }
+                (None, Some(name)) => writer.directive(&name, &value),
+                (None, None) => writer.write_value(&value),
+            },
+            Event::GroupStart => match (key.take(), condition.take()) {
+                (Some(key), Some(condition)) => writer.begin_conditional_group(&key, &condition),
+                (Some(key), None) => writer.begin_group(&key),
+                (None, _) => writer.begin_unnamed_group(),
+            },
+            Event::GroupEnd => writer.end_group(),
+            Event::Comment(comment) => {
+                let comment = comment.strip_prefix(' ').unwrap_or(&comment);
+                let same_line = matches!(last_end, Some(end) if !text[end..span.start].contains('\n'));
+                if same_line && key.is_none() && directive.is_none() {
+                    writer.trailing_comment(comment)
+                } else {
+                    writer.comment(comment)
+                }
+            }
+            Event::Conditional(_) => unreachable!("handled above"),
+        };
+        written.map_err(|err| err.to_string())?;
+        last_end = Some(span.end);
+    }
+    if let Some((key, value)) = pair.take() {
+        writer.write_pair(&key, &value).map_err(|err| err.to_string())?;
+    }
+    let mut output = String::from_utf8(writer.into_inner()).map_err(|err| err.to_string())?;
+    output.push('\n');
+    Ok(output)
+}
+
+fn fmt(mut args: Args) -> Result<bool> {
+    let mut all_formatted = true;
+    for file in args.files() {
+        let source = read(&file)?;
+        let output = reformatted(&file, &source.text)?;
+        if args.check {
+            if output != source.text {
+                println!("{}", file);
+                all_formatted = false;
+            }
+        } else if args.write && file != "-" {
+            if output != source.text {
+                write_bytes(&file, &source.encode(&output)?)?;
+            }
+        } else {
+            write("-", &output)?;
+        }
+    }
+    Ok(all_formatted)
+}
+
+fn check(mut args: Args) -> Result<bool> {
+    let mut all_valid = true;
+    for file in args.files() {
+        let text = read(&file)?.text;
+        if let Err(err) = parse(&file, &text) {
+            println!("{}", err);
+            all_valid = false;
+        }
+    }
+    Ok(all_valid)
+}
+
+fn get(mut args: Args) -> Result<bool> {
+    let query = Query::new(&args.required("path")?).map_err(|err| err.to_string())?.case_insensitive(args.case_insensitive);
+    let value = read_value(&args.file()?)?;
+    let found = query.matches(&value);
+    for found in &found {
+        if args.paths {
+            print!("{}\t", found.path);
+        }
+        match found.value {
+            Value::String(text) => println!("{}", text),
+            Value::Group(group) => {
+                let mut writer = Writer::new(Vec::new());
+                writer.begin_unnamed_group().map_err(|err| err.to_string())?;
+                write_entries(&mut writer, group).map_err(|err| err.to_string())?;
+                writer.end_group().map_err(|err| err.to_string())?;
+                println!("{}", String::from_utf8_lossy(&writer.into_inner()));
+            }
+        }
+    }
+    Ok(!found.is_empty())
+}
+
+fn write_entries(writer: &mut Writer<Vec<u8>>, group: &Map<String, Value>) -> vdf_serde::Result<()> {
+    for (key, value) in group {
+        match value {
+            Value::String(text) => writer.write_pair(key, text)?,
+            Value::Group(inner) => {
+                writer.begin_group(key)?;
+                write_entries(writer, inner)?;
+                writer.end_group()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn set(mut args: Args) -> Result<bool> {
+    let path = args.required("path")?;
+    let new_value = Value::from(args.required("value")?);
+    let file = args.file()?;
+    let source = read(&file)?;
+    parse(&file, &source.text)?;
+    let mut document: Document = source.text.parse().map_err(|err| format!("{}: {}", file, err))?;
+
+    let query = Query::new(&path).map_err(|err| err.to_string())?.case_insensitive(args.case_insensitive);
+    let found = query.matches(document.value()).into_iter().map(|found| found.path).collect::<Vec<_>>();
+    let ops = if found.is_empty() {
+        additions(document.value(), &path, args.case_insensitive, new_value)?
+    } else {
+        found.into_iter().map(|path| PatchOp::Replace { path, old: None, value: new_value.clone() }).collect()
+    };
+    Patch { ops }.apply_to_document(&mut document).map_err(|err| err.to_string())?;
+
+    if args.write && file != "-" {
+        write_bytes(&file, &source.encode(document.as_str())?)?;
+    } else {
+        write("-", document.as_str())?;
+    }
+    Ok(true)
+}
+
+/// Adds a path that matches nothing, under everything the deepest part of it that's there matches,
+/// with each missing group on the way to the new value added as a new group
+fn additions(root: &Value, path: &str, case_insensitive: bool, new_value: Value) -> Result<Vec<PatchOp>> {
+    // where the slashes between keys are, skipping escaped ones
+    let mut slashes = Vec::new();
+    let mut escaped = false;
+    for (i, c) in path.char_indices() {
+        match (escaped, c) {
+            (true, _) => escaped = false,
+            (false, '\\') => escaped = true,
+            (false, '/') => slashes.push(i),
+            _ => {}
+        }
+    }
+    let mut parents = vec![String::new()];
+    let mut missing = 0;
+    for (n, &slash) in slashes.iter().enumerate().rev() {
+        let query = Query::new(&path[..slash]).map_err(|err| err.to_string())?.case_insensitive(case_insensitive);
+        let found = query.matches(root);
+        if !found.is_empty() {
+            parents = found.into_iter().map(|found| found.path).collect();
+            missing = n + 1;
+            break;
+        }
+    }
+
+    let mut ops = Vec::new();
+    for parent in parents {
+        let start = if missing == 0 { 0 } else { slashes[missing - 1] + 1 };
+        let ends = slashes[missing..].iter().copied().chain(Some(path.len()));
+        for end in ends {
+            let added = if parent.is_empty() { path[start..end].to_string() } else { format!("{}/{}", parent, &path[start..end]) };
+            let value = if end == path.len() { new_value.clone() } else { Value::default() };
+            ops.push(PatchOp::Add { path: added, value });
+        }
+    }
+    Ok(ops)
+}
+
+fn to_json(mut args: Args) -> Result<bool> {
+    let file = args.file()?;
+    let text = read(&file)?.text;
+    parse(&file, &text)?;
+    let document: Document = text.parse().map_err(|err| format!("{}: {}", file, err))?;
+    let json = vdf_serde::json::document_to_string_pretty(&document).map_err(|err| err.to_string())?;
+    println!("{}", json);
+    Ok(true)
+}
+
+fn from_json(mut args: Args) -> Result<bool> {
+    let file = args.file()?;
+    let document = vdf_serde::json::document_from_str(&read(&file)?.text).map_err(|err| format!("{}: {}", file, err))?;
+    write("-", &format!("{}\n", document))?;
+    Ok(true)
+}
+
+fn diff(mut args: Args) -> Result<bool> {
+    let (old, new) = (args.required("old")?, args.required("new")?);
+    if let Some(extra) = args.positional.first() {
+        return Err(format!("unexpected argument {:?}", extra));
+    }
+    if old == "-" && new == "-" {
+        return Err("<old> and <new> can't both be stdin".to_string());
+    }
+    let (old, new) = (read_value(&old)?, read_value(&new)?);
+    let diff = vdf_serde::diff(&old, &new);
+    print!("{}", diff);
+    Ok(diff.is_empty())
+}
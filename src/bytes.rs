@@ -0,0 +1,165 @@
+//! Text encodings for byte arrays, since VDF can only hold strings
+
+use crate::error::{Error, Result};
+
+/// How byte arrays are written as VDF strings
+///
+/// Byte arrays are only supported once one of these has been picked, with
+/// [`Serializer::byte_encoding`](struct.Serializer.html#method.byte_encoding) and
+/// [`Deserializer::byte_encoding`](struct.Deserializer.html#method.byte_encoding).
+/// Since a plain `Vec<u8>` looks like any other sequence to Serde, fields need
+/// something like [`serde_bytes`](https://docs.rs/serde_bytes) to be treated as bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ByteEncoding {
+    /// Two hexadecimal digits per byte, written in lowercase and read in either case
+    Hex,
+    /// Two hexadecimal digits per byte, written in uppercase and read in either case
+    UpperHex,
+    /// Standard base64 with padding, which is optional when reading but has to be right if it's there
+    Base64,
+    /// Uppercase hexadecimal split into lines of 40 bytes, with a line break in front of each line,
+    /// read with any whitespace between digits
+    ///
+    /// The line breaks are part of the string, so with the `escape` feature on they're written as
+    /// `\n` escape sequences, and they're only written as actual line breaks with it off.
+    Blob,
+}
+
+/// How many bytes go on each line of a [`ByteEncoding::Blob`](enum.ByteEncoding.html#variant.Blob)
+const BLOB_LINE_BYTES: usize = 40;
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl ByteEncoding {
+    pub(crate) fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            ByteEncoding::Hex => bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            ByteEncoding::UpperHex => bytes.iter().map(|byte| format!("{:02X}", byte)).collect(),
+            ByteEncoding::Blob => {
+                let mut output = String::new();
+                for line in bytes.chunks(BLOB_LINE_BYTES) {
+                    output.push('\n');
+                    output.push_str(&ByteEncoding::UpperHex.encode(line));
+                }
+                output
+            }
+            ByteEncoding::Base64 => {
+                let mut output = String::with_capacity(bytes.len().div_ceil(3) * 4);
+                for chunk in bytes.chunks(3) {
+                    let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| group | u32::from(byte) << (16 - 8 * i));
+                    for i in 0..4 {
+                        if i <= chunk.len() {
+                            output.push(BASE64_ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+                        } else {
+                            output.push('=');
+                        }
+                    }
+                }
+                output
+            }
+        }
+    }
+
+    pub(crate) fn decode(self, text: &str) -> Result<Vec<u8>> {
+        match self {
+            ByteEncoding::Hex | ByteEncoding::UpperHex => {
+                if text.len() % 2 == 1 {
+                    return Err(Error::StringParse(format!("hex byte array has an odd number of digits ({})", text.len())));
+                }
+                let digit = |i: usize| {
+                    let c = text.as_bytes()[i] as char;
+                    c.to_digit(16).map(|d| d as u8).ok_or_else(|| Error::StringParse(format!("invalid hex digit {:?} at index {} of byte array", c, i)))
+                };
+                (0..text.len()).step_by(2).map(|i| Ok(digit(i)? << 4 | digit(i + 1)?)).collect()
+            }
+            ByteEncoding::Blob => {
+                let digits: String = text.split_ascii_whitespace().collect();
+                ByteEncoding::Hex.decode(&digits)
+            }
+            ByteEncoding::Base64 => {
+                let data = text.trim_end_matches('=');
+                let padding = text.len() - data.len();
+                if data.len() % 4 == 1 || (padding > 0 && padding != 4 - data.len() % 4) {
+                    return Err(Error::StringParse(format!("base64 byte array has an invalid length or padding ({})", text.len())));
+                }
+                let mut output = Vec::with_capacity(data.len() * 3 / 4);
+                let mut group = 0u32;
+                for (i, c) in data.bytes().enumerate() {
+                    let value = BASE64_ALPHABET.iter().position(|&a| a == c)
+                        .ok_or_else(|| Error::StringParse(format!("invalid base64 character {:?} at index {} of byte array", c as char, i)))?;
+                    group = group << 6 | value as u32;
+                    if i % 4 == 3 {
+                        output.extend_from_slice(&group.to_be_bytes()[1..]);
+                        group = 0;
+                    }
+                }
+                let (leftover, unused_bits) = match data.len() % 4 {
+                    2 => (1, 4),
+                    3 => (2, 2),
+                    _ => (0, 0),
+                };
+                if group & ((1 << unused_bits) - 1) != 0 {
+                    return Err(Error::StringParse(format!("base64 byte array has leftover bits at the end ({:?})", text)));
+                }
+                output.extend_from_slice(&(group >> unused_bits).to_be_bytes()[4 - leftover..]);
+                Ok(output)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for bytes in [&b""[..], b"f", b"fo", b"foo", b"foob", b"fooba", b"foobar", &[0, 255, 16, 128]].iter() {
+            for &encoding in &[ByteEncoding::Hex, ByteEncoding::UpperHex, ByteEncoding::Base64, ByteEncoding::Blob] {
+                assert_eq!(encoding.decode(&encoding.encode(bytes)).unwrap(), *bytes);
+            }
+        }
+        assert_eq!(ByteEncoding::Base64.encode(b"fooba"), "Zm9vYmE=");
+        assert_eq!(ByteEncoding::Base64.decode("Zm9vYmE").unwrap(), b"fooba");
+        assert_eq!(ByteEncoding::UpperHex.encode(&[0xab, 0x01]), "AB01");
+        assert_eq!(ByteEncoding::Hex.decode("aB01").unwrap(), [0xab, 0x01]);
+
+        assert!(ByteEncoding::Hex.decode("abc").is_err());
+        assert!(ByteEncoding::Hex.decode("zz").is_err());
+        assert!(ByteEncoding::Base64.decode("Zm9vY").is_err());
+        assert!(ByteEncoding::Base64.decode("Zm9v!mE=").is_err());
+    }
+
+    #[test]
+    fn test_base64_padding() {
+        assert_eq!(ByteEncoding::Base64.decode("Zg==").unwrap(), b"f");
+        assert_eq!(ByteEncoding::Base64.decode("Zg").unwrap(), b"f");
+        assert_eq!(ByteEncoding::Base64.decode("Zm8=").unwrap(), b"fo");
+        for bad in &["Zm9v=", "Zm9v==", "Zg=", "Zm8==", "Zg===", "Zh==", "Zm9=", "="] {
+            assert!(ByteEncoding::Base64.decode(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_blob() {
+        let bytes: Vec<u8> = (0..45).collect();
+        let blob = ByteEncoding::Blob.encode(&bytes);
+        assert_eq!(blob.lines().map(str::len).collect::<Vec<_>>(), [0, 80, 10]);
+        assert!(blob.ends_with("2A2B2C"));
+        assert_eq!(ByteEncoding::Blob.encode(b""), "");
+        assert_eq!(ByteEncoding::Blob.decode("\n\t\t0A0b\n\t\tFF\n\t").unwrap(), [0x0a, 0x0b, 0xff]);
+        assert!(ByteEncoding::Blob.decode("0A 0").is_err());
+
+        // the newlines survive being written as a VDF string and read back
+        use serde::{Deserialize, Serialize};
+        let mut serializer = crate::Serializer::new().byte_encoding(ByteEncoding::Blob);
+        serde_bytes::Bytes::new(&bytes[..2]).serialize(&mut serializer).unwrap();
+        let expected = if cfg!(feature = "escape") { "\"\\n0001\"" } else { "\"\n0001\"" };
+        assert_eq!(serializer.into_string(), expected);
+        let mut serializer = crate::Serializer::new().byte_encoding(ByteEncoding::Blob);
+        serde_bytes::Bytes::new(&bytes).serialize(&mut serializer).unwrap();
+        let text = serializer.into_string();
+        let mut deserializer = crate::Deserializer::from_str(&text).byte_encoding(ByteEncoding::Blob);
+        assert_eq!(serde_bytes::ByteBuf::deserialize(&mut deserializer).unwrap(), bytes);
+    }
+}
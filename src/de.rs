@@ -1,35 +1,330 @@
 //! Deserialize VDF data to a Rust data structure
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use serde::Deserialize;
-use serde::de::{self, Visitor, MapAccess, DeserializeSeed, IntoDeserializer};
-use steamy_vdf::parser::{self as vdf_parser, Token};
+use serde::de::DeserializeOwned;
+use serde::de::{self, Visitor, MapAccess, SeqAccess, DeserializeSeed, IntoDeserializer};
+use steamy_vdf::parser::Token;
 
-use crate::error::{Error, Result};
+use crate::bytes::ByteEncoding;
+use crate::encoding::{decode, Encoding};
+use crate::error::{Error, Position, Result};
+use crate::reader::{next_raw_token, RawToken};
 use std::str::FromStr;
-use std::borrow::{Borrow, Cow};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+/// What to do when the same key shows up more than once in a group
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum DuplicateKeys {
+    /// Hand every entry to the `Deserialize` implementation as it appears, so a `HashMap` keeps
+    /// the last value and a derived struct fails with a "duplicate field" error
+    #[default]
+    Passthrough,
+    /// Fail with [`Error::DuplicateKey`](enum.Error.html#variant.DuplicateKey)
+    Error,
+    /// Keep the first entry for each key and skip the rest
+    FirstWins,
+    /// Keep the last entry for each key and skip the rest
+    LastWins,
+    /// Gather every value for a key that's read as a sequence, so a field like `Vec<T>` sees all of
+    /// them, and hand every other entry over as it appears, the same as with `Passthrough`
+    ///
+    /// A key that only appears once can still go into a non-sequence field, but a struct field that
+    /// isn't a sequence fails with [`Error::DuplicateKey`](enum.Error.html#variant.DuplicateKey) if its
+    /// key repeats. Maps and types that take whatever they're given, like [`Value`](enum.Value.html),
+    /// see every entry in order.
+    Collect,
+}
 
 /// A structure that deserializes VDF into Rust values
 pub struct Deserializer<'de> {
+    original: &'de str,
     input: &'de str,
-    parsed_input: VecDeque<Token<'de>>,
+    parsed_input: VecDeque<(Token<'de>, usize)>,
     top_level: bool,
+    duplicate_keys: DuplicateKeys,
+    lenient_bools: bool,
+    lenient_numbers: bool,
+    case_insensitive: bool,
+    byte_encoding: Option<ByteEncoding>,
+    coercions: Vec<Coercion>,
+    path: Vec<String>,
+    repeatable: Option<Cow<'de, str>>,
+    document_end: bool,
+    duplicates: HashMap<usize, bool>,
+}
+
+/// A number that only parsed because of [lenient number parsing](struct.Deserializer.html#method.lenient_numbers)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Coercion {
+    /// The keys leading to the value, separated by `/`
+    pub path: String,
+    /// The value as it was written
+    pub text: String,
+    /// Where the value starts
+    pub position: Position,
 }
 
 impl<'de> Deserializer<'de> {
     /// Creates a VDF deserializer from a `&str`
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &'de str) -> Self {
         Self {
+            original: input,
             input,
             parsed_input: VecDeque::new(),
             top_level: true,
+            duplicate_keys: DuplicateKeys::default(),
+            lenient_bools: false,
+            lenient_numbers: false,
+            case_insensitive: false,
+            byte_encoding: None,
+            coercions: Vec::new(),
+            path: Vec::new(),
+            repeatable: None,
+            document_end: false,
+            duplicates: HashMap::new(),
+        }
+    }
+
+    /// Sets how keys that appear more than once in the same group are handled
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use vdf_serde::{Deserializer, DuplicateKeys};
+    /// use serde::Deserialize;
+    ///
+    /// let mut deserializer = Deserializer::from_str("{ \"a\" \"1\" \"a\" \"2\" }")
+    ///     .duplicate_keys(DuplicateKeys::FirstWins);
+    /// let data = HashMap::<String, u8>::deserialize(&mut deserializer)?;
+    /// deserializer.end()?;
+    /// assert_eq!(data["a"], 1);
+    /// # Ok::<(), vdf_serde::Error>(())
+    /// ```
+    ///
+    /// `Error`, `FirstWins` and `LastWins` need to see every key in a group before handing any of it
+    /// over, so each group a map or struct is read from is held in memory whole, nested groups
+    /// included. For something as big as the `world` group of a `.vmf`, `Passthrough` and `Collect`
+    /// don't do that, except that `Collect` holds what's left of a group once it starts gathering
+    /// a key's values into a sequence.
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Sets whether bools can also be written as `"true"`/`"false"`, `"yes"`/`"no"`, or `""`
+    ///
+    /// These are matched case-insensitively, and an empty string counts as `false`. By default,
+    /// only `"0"` and `"1"` are accepted.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use vdf_serde::Deserializer;
+    /// use serde::Deserialize;
+    ///
+    /// let mut deserializer = Deserializer::from_str("{ \"a\" \"Yes\" \"b\" \"\" }")
+    ///     .lenient_bools(true);
+    /// let data = HashMap::<String, bool>::deserialize(&mut deserializer)?;
+    /// assert!(data["a"] && !data["b"]);
+    /// # Ok::<(), vdf_serde::Error>(())
+    /// ```
+    pub fn lenient_bools(mut self, lenient: bool) -> Self {
+        self.lenient_bools = lenient;
+        self
+    }
+
+    /// Sets whether numbers are read the forgiving way Valve's `atoi` and `atof` read them
+    ///
+    /// When a value isn't a plain number, leading whitespace is skipped, a `0x` prefix means
+    /// hexadecimal, reading stops at the first character that doesn't fit (so `"5.0"` is `5`
+    /// as an integer and `"60fps"` is `60`), an empty or non-numeric value is `0`, and integers
+    /// that don't fit the type are clamped to its range. Every value this happens to is
+    /// recorded, and can be looked at afterwards with [`coercions`](#method.coercions).
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use vdf_serde::Deserializer;
+    /// use serde::Deserialize;
+    ///
+    /// let mut deserializer = Deserializer::from_str("{ \"fps_max\" \"60fps\" \"rate\" \"0x10\" \"ok\" \"1\" }")
+    ///     .lenient_numbers(true);
+    /// let data = HashMap::<String, u32>::deserialize(&mut deserializer)?;
+    /// assert_eq!((data["fps_max"], data["rate"]), (60, 16));
+    /// let coerced = deserializer.coercions().iter().map(|c| c.path.as_str()).collect::<Vec<_>>();
+    /// assert_eq!(coerced, ["fps_max", "rate"]);
+    /// # Ok::<(), vdf_serde::Error>(())
+    /// ```
+    pub fn lenient_numbers(mut self, lenient: bool) -> Self {
+        self.lenient_numbers = lenient;
+        self
+    }
+
+    /// Sets whether struct fields and top-level names match keys regardless of ASCII case
+    ///
+    /// Valve's own parser doesn't care about case, so files in the wild have `"LibraryFolders"` in one
+    /// place and `"libraryfolders"` in another. A key written two different ways in the same group
+    /// counts as the same field twice, and keys of maps are left as they're written.
+    ///
+    /// Keys are renamed before the struct sees them, so each group a struct is read from is held in
+    /// memory whole, nested groups included.
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use vdf_serde::Deserializer;
+    ///
+    /// #[derive(Deserialize)]
+    /// #[serde(rename = "AppState")]
+    /// struct AppState {
+    ///     #[serde(rename = "StateFlags")]
+    ///     state_flags: u32,
+    /// }
+    ///
+    /// let mut deserializer = Deserializer::from_str(r#""appstate" { "stateflags" "4" }"#)
+    ///     .case_insensitive(true);
+    /// assert_eq!(AppState::deserialize(&mut deserializer)?.state_flags, 4);
+    /// # Ok::<(), vdf_serde::Error>(())
+    /// ```
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Sets whether the input is just the entries of the top-level group, with no name or braces around them
+    ///
+    /// This is how Hammer's `.vmf` files are laid out, so the whole input is read as one group.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use serde::Deserialize;
+    /// use vdf_serde::Deserializer;
+    ///
+    /// let mut deserializer = Deserializer::from_str("cameras { activecamera -1 }\ncordon { active 0 }")
+    ///     .bare_top_level(true);
+    /// let data = HashMap::<String, HashMap<String, i32>>::deserialize(&mut deserializer)?;
+    /// deserializer.end()?;
+    /// assert_eq!(data["cameras"]["activecamera"], -1);
+    /// # Ok::<(), vdf_serde::Error>(())
+    /// ```
+    pub fn bare_top_level(mut self, bare: bool) -> Self {
+        self.top_level = !bare;
+        self.document_end = bare;
+        self.parsed_input.clear();
+        if bare {
+            self.parsed_input.push_back((Token::GroupStart, 0));
         }
+        self
     }
+
+    /// Returns every value that [lenient number parsing](#method.lenient_numbers) had to coerce so far
+    pub fn coercions(&self) -> &[Coercion] {
+        &self.coercions
+    }
+
+    /// Sets how byte arrays are read from strings, which is required for byte arrays to work at all
+    ///
+    /// ```
+    /// use serde::Deserialize;
+    /// use vdf_serde::{ByteEncoding, Deserializer};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Depot {
+    ///     #[serde(with = "serde_bytes")]
+    ///     sha: Vec<u8>,
+    /// }
+    ///
+    /// let mut deserializer = Deserializer::from_str("\"Depot\" { \"sha\" \"c0ffee\" }")
+    ///     .byte_encoding(ByteEncoding::Hex);
+    /// assert_eq!(Depot::deserialize(&mut deserializer)?.sha, [0xc0, 0xff, 0xee]);
+    /// # Ok::<(), vdf_serde::Error>(())
+    /// ```
+    pub fn byte_encoding(mut self, encoding: ByteEncoding) -> Self {
+        self.byte_encoding = Some(encoding);
+        self
+    }
+
+    /// Makes sure nothing but whitespace and comments is left in the input
+    ///
+    /// This should be called after deserializing a value with a `Deserializer` you built yourself,
+    /// since [`from_str`](fn.from_str.html) does it for you.
+    pub fn end(&mut self) -> Result<()> {
+        if self.parsed_input.is_empty() && self.read_token()?.is_none() {
+            Ok(())
+        } else {
+            Err(Error::LateEOF)
+        }
+    }
+}
+
+/// Deserialize an instance of type `T` from VDF text in UTF-8, UTF-16LE or UTF-16BE, with or without a byte order mark
+///
+/// The encoding is worked out with [`Encoding::detect`](enum.Encoding.html#method.detect).
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let utf16: Vec<u8> = "\u{feff}{ \"greeting\" \"h\u{e9}llo\" }".encode_utf16().flat_map(u16::to_le_bytes).collect();
+/// let data: HashMap<String, String> = vdf_serde::from_bytes(&utf16)?;
+/// assert_eq!(data["greeting"], "h\u{e9}llo");
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If `bytes` aren't valid text in the encoding they seem to be in, an
+/// [`Error::Encoding`](enum.Error.html#variant.Encoding) will be returned, and otherwise
+/// the same errors as [`from_str`](fn.from_str.html) can be.
+pub fn from_bytes<T>(bytes: &[u8]) -> Result<T> where T: DeserializeOwned {
+    from_str(&decode(bytes)?)
+}
+
+/// Deserialize an instance of type `T` from VDF text in a particular encoding
+///
+/// A byte order mark for `encoding` at the start of `bytes` is skipped.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use vdf_serde::Encoding;
+///
+/// let data: HashMap<String, String> = vdf_serde::from_bytes_with_encoding(b"{ \"name\" \"Caf\xe9\" }", Encoding::Windows1252)?;
+/// assert_eq!(data["name"], "Caf\u{e9}");
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If `bytes` aren't valid text in `encoding`, an [`Error::Encoding`](enum.Error.html#variant.Encoding)
+/// with the offset of the first invalid byte will be returned, and otherwise the same errors as
+/// [`from_str`](fn.from_str.html) can be.
+pub fn from_bytes_with_encoding<T>(bytes: &[u8], encoding: Encoding) -> Result<T> where T: DeserializeOwned {
+    from_str(&encoding.decode(bytes)?)
 }
 
 /// Deserialize an instance of type `T` from a string of VDF text
 ///
+/// Strings without escape sequences in them are borrowed straight from `s`, so `&str` and
+/// `#[serde(borrow)] Cow<str>` fields avoid allocating. A `&str` field can't hold a string that
+/// had escapes in it, though, so `Cow<str>` is the safer choice unless the data is known to be free of them.
+///
+/// ```
+/// use std::borrow::Cow;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct AppState<'a> {
+///     name: &'a str,
+///     #[serde(borrow)]
+///     installdir: Cow<'a, str>,
+/// }
+///
+/// let vdf_data = r#""AppState" { "name" "Portal 2" "installdir" "Portal\\2" }"#;
+/// let app: AppState = vdf_serde::from_str(vdf_data)?;
+/// assert_eq!(app.name, "Portal 2");
+/// assert!(matches!(app.installdir, Cow::Owned(_)));
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+///
 /// # Errors
 ///
 /// If `s` is not valid VDF, or `T` uses an unsupported Serde data type,
@@ -38,27 +333,91 @@ impl<'de> Deserializer<'de> {
 pub fn from_str<'a, T>(s: &'a str) -> Result<T> where T: Deserialize<'a> {
     let mut deserializer = Deserializer::from_str(s);
     let t = T::deserialize(&mut deserializer)?;
-    // before we toss a LateEOF, let's make sure we're not erroring on some whitespace
-    let remaining_input = deserializer.input.trim_end();
-    if remaining_input.is_empty() {
-        Ok(t)
-    } else {
-        Err(Error::LateEOF)
+    deserializer.end()?;
+    Ok(t)
+}
+
+/// Reads an integer from the start of `data` like C's `atoi`, but with hexadecimal support
+/// and saturating instead of overflowing
+fn atoi(data: &str) -> i128 {
+    let data = data.trim_start();
+    let (negative, data) = match data.as_bytes().first() {
+        Some(b'-') => (true, &data[1..]),
+        Some(b'+') => (false, &data[1..]),
+        _ => (false, data),
+    };
+    let (radix, digits) = match data.get(..2) {
+        Some("0x") | Some("0X") => (16, &data[2..]),
+        _ => (10, data),
+    };
+    let value = digits.chars()
+        .map_while(|c| c.to_digit(radix))
+        .fold(0i128, |value, digit| value.saturating_mul(i128::from(radix)).saturating_add(i128::from(digit)));
+    if negative { -value } else { value }
+}
+
+/// Reads a float from the start of `data` like C's `atof`
+fn atof(data: &str) -> f64 {
+    let data = data.trim_start();
+    let bytes = data.as_bytes();
+    let digits_from = |start: usize| start + bytes[start..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let mut end = match bytes.first() {
+        Some(b'-') | Some(b'+') => 1,
+        _ => 0,
+    };
+    if matches!(data.get(end..end + 2), Some("0x") | Some("0X")) {
+        return atoi(data) as f64;
+    }
+    end = digits_from(end);
+    if bytes.get(end) == Some(&b'.') {
+        end = digits_from(end + 1);
+    }
+    if let Some(b'e') | Some(b'E') = bytes.get(end) {
+        let exponent_start = match bytes.get(end + 1) {
+            Some(b'-') | Some(b'+') => end + 2,
+            _ => end + 1,
+        };
+        let exponent_end = digits_from(exponent_start.min(bytes.len()));
+        if exponent_end > exponent_start {
+            end = exponent_end;
+        }
     }
+    data[..end].parse().unwrap_or(0.0)
+}
+
+/// One key and the tokens of its value, read ahead of time so duplicates can be resolved
+struct Entry<'de> {
+    key: Cow<'de, str>,
+    offset: usize,
+    tokens: Vec<(Token<'de>, usize)>,
 }
 
 impl<'de> Deserializer<'de> {
-    fn parse_more(&mut self) -> Result<()> {
-        let parsed = vdf_parser::next(self.input.as_bytes());
-        match parsed {
-            nom::IResult::Done(remainder, token) => {
-                // since it came from `as_bytes` this is safe
-                self.input = unsafe { std::str::from_utf8_unchecked(remainder) };
-                self.parsed_input.push_back(token);
+    /// Reads the next token that isn't a comment, if there is one
+    ///
+    /// While the whole input is being read as a group, directives come back as keys starting with `#`,
+    /// and the end of the input comes back as a `}`.
+    fn read_token(&mut self) -> Result<Option<(Token<'de>, usize)>> {
+        loop {
+            match next_raw_token(self.original, &mut self.input)? {
+                Some((RawToken::Token(Token::Statement(name)), span)) if self.document_end => {
+                    return Ok(Some((Token::Item(Cow::Owned(format!("#{}", name))), span.start)));
+                }
+                Some((RawToken::Token(token), span)) => return Ok(Some((token, span.start))),
+                // conditions aren't evaluated, so every variant of a pair is read
+                Some((RawToken::Comment(_), _)) | Some((RawToken::Conditional(_), _)) => continue,
+                None if self.document_end => {
+                    self.document_end = false;
+                    return Ok(Some((Token::GroupEnd, self.original.len())));
+                }
+                None => return Ok(None),
             }
-            nom::IResult::Incomplete(_) => return Err(Error::EarlyEOF),
-            nom::IResult::Error(err) => return Err(Error::Tokenize(err.to_string())),
         }
+    }
+
+    fn parse_more(&mut self) -> Result<()> {
+        let token = self.read_token()?.ok_or(Error::EarlyEOF)?;
+        self.parsed_input.push_back(token);
         Ok(())
     }
 
@@ -71,14 +430,18 @@ impl<'de> Deserializer<'de> {
 
     fn peek_token(&mut self) -> Result<&Token<'de>> {
         self.parse_more_if_needed()?;
-        self.parsed_input.get(0).ok_or(Error::EarlyEOF)
+        self.parsed_input.front().map(|(token, _)| token).ok_or(Error::EarlyEOF)
     }
 
-    fn next_token(&mut self) -> Result<Token<'de>> {
+    fn next_token_with_offset(&mut self) -> Result<(Token<'de>, usize)> {
         self.parse_more_if_needed()?;
         self.parsed_input.pop_front().ok_or(Error::EarlyEOF)
     }
 
+    fn next_token(&mut self) -> Result<Token<'de>> {
+        self.next_token_with_offset().map(|(token, _)| token)
+    }
+
     fn next_token_item(&mut self) -> Result<Cow<'de, str>> {
         match self.next_token()? {
             Token::Item(data) => Ok(data),
@@ -89,61 +452,378 @@ impl<'de> Deserializer<'de> {
     fn parse_next_token_data<T: FromStr>(&mut self) -> Result<T> where T::Err : std::fmt::Display {
         self.next_token_item()?.parse().map_err(|err: T::Err| Error::StringParse(err.to_string()))
     }
+
+    /// Reads the next item as a `T`, falling back to `lenient` if that's turned on
+    fn parse_next_number<T: FromStr>(&mut self, lenient: impl FnOnce(&str) -> T) -> Result<T> where T::Err : std::fmt::Display {
+        let (token, offset) = self.next_token_with_offset()?;
+        let data = match token {
+            Token::Item(data) => data,
+            got => return Err(Error::Expected("Item", format!("{:?}", got))),
+        };
+        match data.parse() {
+            Ok(value) => Ok(value),
+            Err(_) if self.lenient_numbers => {
+                self.coercions.push(Coercion {
+                    path: self.path.join("/"),
+                    text: data.to_string(),
+                    position: Position::from_offset(self.original, offset),
+                });
+                Ok(lenient(&data))
+            }
+            Err(err) => Err(Error::StringParse(err.to_string())),
+        }
+    }
+
+    fn parse_next_integer<T>(&mut self, min: i128, max: i128) -> Result<T> where T: FromStr + TryFrom<i128>, T::Err : std::fmt::Display {
+        self.parse_next_number(|data| T::try_from(atoi(data).clamp(min, max)).unwrap_or_else(|_| unreachable!("clamped to fit")))
+    }
+
+    /// At the top level, a named type's value comes after its name
+    fn expect_name(&mut self, name: &'static str) -> Result<()> {
+        if self.top_level {
+            match self.next_token()? {
+                Token::Item(name_token) if name_token == name => {},
+                Token::Item(name_token) if self.case_insensitive && name_token.eq_ignore_ascii_case(name) => {},
+                got => return Err(Error::Expected(name, format!("{:?}", got))),
+            }
+            self.top_level = false;
+        }
+        Ok(())
+    }
+
+    fn duplicate_key(&self, key: &str, first: usize, second: usize) -> Error {
+        Error::DuplicateKey {
+            key: key.to_string(),
+            first: Position::from_offset(self.original, first),
+            second: Position::from_offset(self.original, second),
+        }
+    }
+
+    /// Reads the rest of a group whose `{` has already been consumed, up to and including its `}`
+    fn scan_group(&mut self) -> Result<(Vec<Entry<'de>>, (Token<'de>, usize))> {
+        let mut entries = Vec::new();
+        loop {
+            let (token, offset) = self.next_token_with_offset()?;
+            let key = match token {
+                Token::GroupEnd => return Ok((entries, (token, offset))),
+                Token::Item(ref key) => key.clone(),
+                got => return Err(Error::Expected("Item", format!("{:?}", got))),
+            };
+            let mut tokens = vec![(token, offset)];
+            let mut depth = 0usize;
+            loop {
+                let (token, token_offset) = self.next_token_with_offset()?;
+                match token {
+                    Token::GroupStart => depth += 1,
+                    Token::GroupEnd if depth == 0 => return Err(Error::Expected("Item or '{'", format!("{:?}", token))),
+                    Token::GroupEnd => depth -= 1,
+                    _ => {}
+                }
+                tokens.push((token, token_offset));
+                if depth == 0 {
+                    break;
+                }
+            }
+            entries.push(Entry { key, offset, tokens });
+        }
+    }
+
+    /// Reads ahead through a group, applies the duplicate key policy, and puts back what's left
+    fn resolve_duplicates(&mut self) -> Result<()> {
+        let (entries, group_end) = self.scan_group()?;
+        let kept: Vec<Entry<'de>> = match self.duplicate_keys {
+            DuplicateKeys::Passthrough | DuplicateKeys::Collect => entries,
+            DuplicateKeys::Error => {
+                let mut seen = HashMap::new();
+                for entry in &entries {
+                    if let Some(&first) = seen.get(&entry.key) {
+                        return Err(self.duplicate_key(&entry.key, first, entry.offset));
+                    }
+                    seen.insert(entry.key.clone(), entry.offset);
+                }
+                entries
+            }
+            DuplicateKeys::FirstWins => {
+                let mut seen = HashSet::new();
+                entries.into_iter().filter(|entry| seen.insert(entry.key.clone())).collect()
+            }
+            DuplicateKeys::LastWins => {
+                let last = entries.iter().enumerate().map(|(i, entry)| (entry.key.clone(), i)).collect::<HashMap<_, _>>();
+                entries.into_iter().enumerate().filter(|(i, entry)| last[&entry.key] == *i).map(|(_, entry)| entry).collect()
+            }
+        };
+        self.unread(kept, group_end);
+        Ok(())
+    }
+
+    /// Takes the values of every entry left in the current group that has `key`, and puts back the rest
+    ///
+    /// Each value still has its copy of the key in front of it.
+    fn take_repeats(&mut self, key: &str) -> Result<VecDeque<Vec<(Token<'de>, usize)>>> {
+        let (entries, group_end) = self.scan_group()?;
+        let (repeats, rest): (Vec<_>, Vec<_>) = entries.into_iter().partition(|entry| entry.key == key);
+        self.unread(rest, group_end);
+        Ok(repeats.into_iter().map(|entry| entry.tokens).collect())
+    }
+
+    /// Renames keys in the group that's about to be read to the struct fields they match regardless of case
+    fn match_fields(&mut self, fields: &'static [&'static str]) -> Result<()> {
+        match self.next_token_with_offset()? {
+            (Token::GroupStart, offset) => {
+                let (mut entries, group_end) = self.scan_group()?;
+                for entry in &mut entries {
+                    if fields.contains(&&*entry.key) {
+                        continue;
+                    }
+                    if let Some(field) = fields.iter().find(|field| field.eq_ignore_ascii_case(&entry.key)) {
+                        entry.key = Cow::Borrowed(field);
+                        entry.tokens[0].0 = Token::Item(Cow::Borrowed(field));
+                    }
+                }
+                self.unread(entries, group_end);
+                self.parsed_input.push_front((Token::GroupStart, offset));
+                Ok(())
+            }
+            (got, _) => Err(Error::Expected("'{'", format!("{:?}", got))),
+        }
+    }
+
+    /// Puts scanned entries back at the front of the input, to be read again
+    fn unread(&mut self, entries: Vec<Entry<'de>>, group_end: (Token<'de>, usize)) {
+        self.parsed_input.push_front(group_end);
+        for entry in entries.into_iter().rev() {
+            for token in entry.tokens.into_iter().rev() {
+                self.parsed_input.push_front(token);
+            }
+        }
+    }
+
+    /// Checks whether any key in the group that was just opened at `start` appears more than once
+    ///
+    /// This looks ahead through a copy of the input without buffering any tokens, and remembers the
+    /// answer for every group nested inside, so each group is only ever looked through once.
+    fn group_has_duplicates(&mut self, start: usize) -> Result<bool> {
+        if let Some(duplicated) = self.duplicates.remove(&start) {
+            return Ok(duplicated);
+        }
+        struct Frame<'de> {
+            start: usize,
+            keys: HashSet<Cow<'de, str>>,
+            duplicated: bool,
+            expecting_value: bool,
+        }
+        let new_frame = |start| Frame { start, keys: HashSet::new(), duplicated: false, expecting_value: false };
+        let mut stack = vec![new_frame(start)];
+        let mut buffered = self.parsed_input.iter().cloned();
+        let mut input = self.input;
+        let mut document_end = self.document_end;
+        loop {
+            let (token, offset) = match buffered.next() {
+                Some(token) => token,
+                None => match next_raw_token(self.original, &mut input)? {
+                    Some((RawToken::Token(token), span)) => (token, span.start),
+                    Some((RawToken::Comment(_), _)) | Some((RawToken::Conditional(_), _)) => continue,
+                    None if document_end => {
+                        document_end = false;
+                        (Token::GroupEnd, self.original.len())
+                    }
+                    None => return Err(Error::EarlyEOF),
+                },
+            };
+            let frame = stack.last_mut().expect("the outermost group ends the loop");
+            match token {
+                Token::Item(_) if frame.expecting_value => frame.expecting_value = false,
+                Token::Item(key) => {
+                    frame.duplicated |= !frame.keys.insert(key);
+                    frame.expecting_value = true;
+                }
+                Token::Statement(name) => {
+                    frame.duplicated |= !frame.keys.insert(Cow::Owned(format!("#{}", name)));
+                    frame.expecting_value = true;
+                }
+                Token::GroupStart => {
+                    frame.expecting_value = false;
+                    stack.push(new_frame(offset));
+                }
+                Token::GroupEnd => {
+                    let frame = stack.pop().expect("the outermost group ends the loop");
+                    if stack.is_empty() {
+                        return Ok(frame.duplicated);
+                    }
+                    self.duplicates.insert(frame.start, frame.duplicated);
+                }
+            }
+        }
+    }
+
+    /// Visits the group that was just opened as a map, or as a sequence of one-entry maps if keys repeat in it
+    ///
+    /// `Collect` is treated like `Passthrough` here, since a visitor that takes whatever's there, like
+    /// [`Value`](enum.Value.html)'s, never asks for a key's values as a sequence.
+    fn visit_group<V: Visitor<'de>>(&mut self, start: usize, visitor: V) -> Result<V::Value> {
+        let keep_duplicates = matches!(self.duplicate_keys, DuplicateKeys::Passthrough | DuplicateKeys::Collect);
+        let value = if keep_duplicates && self.group_has_duplicates(start)? {
+            visitor.visit_seq(SingleEntries { de: &mut *self })?
+        } else {
+            if !keep_duplicates {
+                self.resolve_duplicates()?;
+            }
+            visitor.visit_map(TabNewlineSeparated::new(self, None))?
+        };
+        match self.next_token()? {
+            Token::GroupEnd => Ok(value),
+            got => Err(Error::Expected("'}'", format!("{:?}", got))),
+        }
+    }
+
+    /// Visits the group that's about to be read as a map, after applying the duplicate key policy
+    ///
+    /// With `Collect`, a struct's `fields` that turn up more than once without being gathered into a
+    /// sequence are an error.
+    fn visit_entries<V: Visitor<'de>>(&mut self, fields: Option<&'static [&'static str]>, visitor: V) -> Result<V::Value> {
+        self.repeatable = None;
+        self.top_level = false;
+        match self.next_token()? {
+            Token::GroupStart => {
+                let fields = match self.duplicate_keys {
+                    DuplicateKeys::Passthrough => None,
+                    DuplicateKeys::Collect => fields,
+                    _ => {
+                        self.resolve_duplicates()?;
+                        None
+                    }
+                };
+                let value = visitor.visit_map(TabNewlineSeparated::new(self, fields))?;
+                match self.next_token()? {
+                    Token::GroupEnd => Ok(value),
+                    got => Err(Error::Expected("'}'", format!("{:?}", got))),
+                }
+            }
+            got => Err(Error::Expected("'{'", format!("{:?}", got))),
+        }
+    }
+
+    /// Visits the whole input as a group of its top-level pairs, or as a string if that's all there is
+    ///
+    /// A document that's a single group in braces with no name in front of it is that group.
+    fn visit_document<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value> {
+        self.document_end = true;
+        let first = self.read_token()?.expect("the end of the input is read as a '}'");
+        match first {
+            (Token::GroupStart, _) => {
+                self.document_end = false;
+                self.parsed_input.push_back(first);
+            }
+            (Token::Item(_), _) => {
+                let second = self.read_token()?.expect("the end of the input is read as a '}'");
+                if !self.document_end {
+                    self.parsed_input.push_back(first);
+                    return de::Deserializer::deserialize_str(self, visitor);
+                }
+                self.parsed_input.extend(vec![(Token::GroupStart, 0), first, second]);
+            }
+            _ => self.parsed_input.extend(vec![(Token::GroupStart, 0), first]),
+        }
+        de::Deserializer::deserialize_any(self, visitor)
+    }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = Error;
 
-    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
-        Err(Error::UnsupportedType("any"))
+    /// Strings are visited as strings, and groups as maps, except that a group where a key repeats
+    /// is visited as a sequence of one-entry maps so that nothing gets lost
+    ///
+    /// The whole input is a group of its top-level pairs, and [`DuplicateKeys`](enum.DuplicateKeys.html)
+    /// settings other than `Passthrough` and `Collect` are applied first.
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.top_level {
+            self.top_level = false;
+            return self.visit_document(visitor);
+        }
+        self.repeatable = None;
+        match self.next_token_with_offset()? {
+            (Token::Item(Cow::Borrowed(data)), _) => visitor.visit_borrowed_str(data),
+            (Token::Item(Cow::Owned(data)), _) => visitor.visit_string(data),
+            (Token::GroupStart, start) => self.visit_group(start, visitor),
+            (got, _) => Err(Error::Expected("Item or '{'", format!("{:?}", got))),
+        }
+    }
+
+    /// Skips over a value without looking at it
+    ///
+    /// This is how keys that a derived struct has no field for get skipped; add
+    /// `#[serde(deny_unknown_fields)]` to the struct to make them an error instead.
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.top_level {
+            return self.deserialize_any(visitor);
+        }
+        self.repeatable = None;
+        let mut depth = 0usize;
+        loop {
+            match self.next_token()? {
+                Token::GroupStart => depth += 1,
+                Token::GroupEnd if depth > 0 => depth -= 1,
+                got @ Token::GroupEnd | got @ Token::Statement(_) => return Err(Error::Expected("Item or '{'", format!("{:?}", got))),
+                Token::Item(_) => {}
+            }
+            if depth == 0 {
+                return visitor.visit_unit();
+            }
+        }
     }
 
     fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         match self.next_token()? {
             Token::Item(data) if data == "0" => visitor.visit_bool(false),
             Token::Item(data) if data == "1" => visitor.visit_bool(true),
+            Token::Item(data) if self.lenient_bools => match data.to_ascii_lowercase().as_str() {
+                "true" | "yes" => visitor.visit_bool(true),
+                "false" | "no" | "" => visitor.visit_bool(false),
+                _ => Err(Error::Expected("bool (\"0\", \"1\", \"true\", \"false\", \"yes\", \"no\", or \"\")", format!("{:?}", Token::Item(data)))),
+            },
             got => Err(Error::Expected("bool (\"0\" or \"1\")", format!("{:?}", got))),
         }
     }
 
     fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_i8(self.parse_next_token_data()?)
+        visitor.visit_i8(self.parse_next_integer(i8::MIN.into(), i8::MAX.into())?)
     }
 
     fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_i16(self.parse_next_token_data()?)
+        visitor.visit_i16(self.parse_next_integer(i16::MIN.into(), i16::MAX.into())?)
     }
 
     fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_i32(self.parse_next_token_data()?)
+        visitor.visit_i32(self.parse_next_integer(i32::MIN.into(), i32::MAX.into())?)
     }
 
     fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_i64(self.parse_next_token_data()?)
+        visitor.visit_i64(self.parse_next_integer(i64::MIN.into(), i64::MAX.into())?)
     }
 
     fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_u8(self.parse_next_token_data()?)
+        visitor.visit_u8(self.parse_next_integer(u8::MIN.into(), u8::MAX.into())?)
     }
 
     fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_u16(self.parse_next_token_data()?)
+        visitor.visit_u16(self.parse_next_integer(u16::MIN.into(), u16::MAX.into())?)
     }
 
     fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_u32(self.parse_next_token_data()?)
+        visitor.visit_u32(self.parse_next_integer(u32::MIN.into(), u32::MAX.into())?)
     }
 
     fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_u64(self.parse_next_token_data()?)
+        visitor.visit_u64(self.parse_next_integer(u64::MIN.into(), u64::MAX.into())?)
     }
 
     fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_f32(self.parse_next_token_data()?)
+        visitor.visit_f32(self.parse_next_number(|data| atof(data) as f32)?)
     }
 
     fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_f64(self.parse_next_token_data()?)
+        visitor.visit_f64(self.parse_next_number(atof)?)
     }
 
     fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
@@ -151,85 +831,110 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     }
 
     fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
-        visitor.visit_str(self.next_token_item()?.borrow())
+        // strings with escapes in them had to be unescaped into a new string, but the rest can borrow
+        match self.next_token_item()? {
+            Cow::Borrowed(data) => visitor.visit_borrowed_str(data),
+            Cow::Owned(data) => visitor.visit_string(data),
+        }
     }
 
     fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         visitor.visit_string(String::from(self.next_token_item()?))
     }
 
-    fn deserialize_bytes<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
-        Err(Error::UnsupportedType("byte array"))
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_byte_buf(visitor)
     }
 
-    fn deserialize_byte_buf<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
-        Err(Error::UnsupportedType("byte array"))
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.byte_encoding {
+            Some(encoding) => visitor.visit_byte_buf(encoding.decode(&self.next_token_item()?)?),
+            None => Err(Error::UnsupportedType("byte array")),
+        }
     }
 
     fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         visitor.visit_some(self)
     }
 
-    fn deserialize_unit<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
-        Err(Error::UnsupportedType("unit"))
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.repeatable = None;
+        match self.next_token()? {
+            Token::Item(data) if data.is_empty() => visitor.visit_unit(),
+            Token::GroupStart => match self.next_token()? {
+                Token::GroupEnd => visitor.visit_unit(),
+                got => Err(Error::Expected("'}'", format!("{:?}", got))),
+            },
+            got => Err(Error::Expected("unit (\"\" or an empty group)", format!("{:?}", got))),
+        }
     }
 
-    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, _visitor: V) -> Result<V::Value> {
-        Err(Error::UnsupportedType("unit_struct"))
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V) -> Result<V::Value> {
+        self.expect_name(name)?;
+        self.deserialize_unit(visitor)
     }
 
     fn deserialize_newtype_struct<V: Visitor<'de>>(self, name: &'static str, visitor: V) -> Result<V::Value> {
-        if self.top_level {
-            let name_token = self.next_token()?;
-            match name_token {
-                Token::Item(name_token) if name_token == name => {},
-                got => return Err(Error::Expected(name, format!("{:?}", got))),
-            }
-            self.top_level = false;
-        }
+        self.expect_name(name)?;
         visitor.visit_newtype_struct(self)
     }
 
-    fn deserialize_seq<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
-        Err(Error::UnsupportedType("seq"))
-    }
-
-    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value> {
-        Err(Error::UnsupportedType("tuple"))
-    }
-
-    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, _visitor: V) -> Result<V::Value> {
-        Err(Error::UnsupportedType("tuple_struct"))
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.repeatable.take() {
+            Some(key) => visitor.visit_seq(RepeatedKey::new(self, key)),
+            None => Err(Error::UnsupportedType("seq")),
+        }
     }
 
-    fn deserialize_map<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value> {
-        match self.next_token()? {
-            Token::GroupStart => {
-                let value = visitor.visit_map(TabNewlineSeparated::new(&mut self))?;
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        self.repeatable = None;
+        self.top_level = false;
+        match self.next_token_with_offset()? {
+            (Token::GroupStart, _) => {
+                let value = visitor.visit_seq(NumberedKeys::new(self))?;
                 match self.next_token()? {
                     Token::GroupEnd => Ok(value),
                     got => Err(Error::Expected("'}'", format!("{:?}", got))),
                 }
             }
-            got => Err(Error::Expected("'{'", format!("{:?}", got))),
+            (Token::Item(data), offset) => {
+                // split the values up and put them back as their own tokens, so they get parsed like any others
+                let values: Vec<Cow<'de, str>> = match data {
+                    Cow::Borrowed(data) => data.split_whitespace().map(Cow::Borrowed).collect(),
+                    Cow::Owned(data) => data.split_whitespace().map(|value| Cow::Owned(value.to_string())).collect(),
+                };
+                if values.len() != len {
+                    return Err(Error::StringParse(format!("expected {} space-separated values, got {}", len, values.len())));
+                }
+                for value in values.into_iter().rev() {
+                    self.parsed_input.push_front((Token::Item(value), offset));
+                }
+                visitor.visit_seq(SpaceSeparated::new(self, len))
+            }
+            (got, _) => Err(Error::Expected("Item or '{'", format!("{:?}", got))),
         }
     }
 
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, name: &'static str, len: usize, visitor: V) -> Result<V::Value> {
+        self.expect_name(name)?;
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.visit_entries(None, visitor)
+    }
+
     fn deserialize_struct<V: Visitor<'de>>(
         self,
         name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V
     ) -> Result<V::Value> {
-        if self.top_level {
-            let name_token = self.next_token()?;
-            match name_token {
-                Token::Item(name_token) if name_token == name => {},
-                got => return Err(Error::Expected(name, format!("{:?}", got))),
-            }
-            self.top_level = false;
+        self.expect_name(name)?;
+        if self.case_insensitive {
+            self.match_fields(fields)?;
         }
-        self.deserialize_map(visitor)
+        self.visit_entries(Some(fields), visitor)
     }
 
     fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value> {
@@ -239,20 +944,22 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
         self.deserialize_str(visitor)
     }
-
-    serde::forward_to_deserialize_any! {
-        ignored_any
-    }
 }
 
 struct TabNewlineSeparated<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
+    key: Option<Cow<'de, str>>,
+    fields: Option<&'static [&'static str]>,
+    seen: HashMap<&'static str, usize>,
 }
 
 impl<'a, 'de> TabNewlineSeparated<'a, 'de> {
-    fn new(de: &'a mut Deserializer<'de>) -> Self {
+    fn new(de: &'a mut Deserializer<'de>, fields: Option<&'static [&'static str]>) -> Self {
         Self {
             de,
+            key: None,
+            fields,
+            seen: HashMap::new(),
         }
     }
 }
@@ -265,20 +972,347 @@ impl<'de, 'a> MapAccess<'de> for TabNewlineSeparated<'a, 'de> {
             K: DeserializeSeed<'de>,
     {
         // Check if there are no more entries.
+        let key = match self.de.peek_token()? {
+            Token::GroupEnd => return Ok(None),
+            Token::Item(key) => Some(key.clone()),
+            _ => None,
+        };
+        // Keep track of where we are, so coerced numbers can say where they were.
+        if let (Some(key), true) = (&key, self.de.lenient_numbers) {
+            self.de.path.push(key.to_string());
+        }
+        // A field whose values weren't gathered up can only be there once.
+        if let (Some(key), Some(fields)) = (&key, self.fields) {
+            if let Some(field) = fields.iter().find(|field| **field == key) {
+                let offset = self.de.parsed_input.front().map_or(0, |(_, offset)| *offset);
+                if let Some(&first) = self.seen.get(field) {
+                    return Err(self.de.duplicate_key(key, first, offset));
+                }
+                self.seen.insert(field, offset);
+            }
+        }
+        if self.de.duplicate_keys == DuplicateKeys::Collect {
+            self.key = key;
+        }
+        // Deserialize a map key.
+        seed.deserialize(MapKey { de: &mut *self.de }).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where
+            V: DeserializeSeed<'de>,
+    {
+        // Deserialize a map value, which might be a whole pile of them if we're collecting duplicates.
+        self.de.repeatable = self.key.take();
+        let value = seed.deserialize(&mut *self.de)?;
+        self.de.repeatable = None;
+        if self.de.lenient_numbers {
+            self.de.path.pop();
+        }
+        Ok(value)
+    }
+}
+
+/// Deserializes map keys, which can only be types that make sense as a single string
+struct MapKey<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+}
+
+macro_rules! forward_to_deserializer {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                self.de.$method(visitor)
+            }
+        )*
+    };
+}
+
+macro_rules! unsupported_key {
+    ($($method:ident => $type:expr,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+                Err(Error::UnsupportedKey($type))
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for MapKey<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.de.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.de.deserialize_str(visitor)
+    }
+
+    forward_to_deserializer! {
+        deserialize_bool
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_char deserialize_str deserialize_string deserialize_identifier
+    }
+
+    unsupported_key! {
+        deserialize_f32 => "f32",
+        deserialize_f64 => "f64",
+        deserialize_bytes => "byte array",
+        deserialize_byte_buf => "byte array",
+        deserialize_unit => "unit",
+        deserialize_seq => "seq",
+        deserialize_map => "map",
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedKey("unit_struct"))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedKey("tuple"))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedKey("tuple_struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedKey("struct"))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        visitor.visit_enum(UnitVariantKey(self.de.next_token_item()?))
+    }
+}
+
+/// A map key read as an enum, which can only be a unit variant
+struct UnitVariantKey<'de>(Cow<'de, str>);
+
+/// What's left of a map key once its variant has been read, which is nothing
+struct UnitVariant;
+
+impl<'de> de::EnumAccess<'de> for UnitVariantKey<'de> {
+    type Error = Error;
+    type Variant = UnitVariant;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, UnitVariant)> {
+        let variant = seed.deserialize(IntoDeserializer::<Error>::into_deserializer(self.0))?;
+        Ok((variant, UnitVariant))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for UnitVariant {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, _seed: T) -> Result<T::Value> {
+        Err(Error::UnsupportedKey("newtype_variant"))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedKey("tuple_variant"))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedKey("struct_variant"))
+    }
+}
+
+/// A tuple written as a group, whose keys have to be `"0"`, `"1"`, and so on, in order
+struct NumberedKeys<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    index: usize,
+}
+
+impl<'a, 'de> NumberedKeys<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>) -> Self {
+        Self {
+            de,
+            index: 0,
+        }
+    }
+}
+
+impl<'de, 'a> SeqAccess<'de> for NumberedKeys<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+        where
+            T: DeserializeSeed<'de>,
+    {
         if self.de.peek_token()? == &Token::GroupEnd {
             return Ok(None);
         }
-        // Deserialize a map key.
+        let key = self.de.next_token_item()?;
+        if key != self.index.to_string() {
+            return Err(Error::Expected("tuple keys \"0\", \"1\", and so on, in order", format!("{:?}", Token::Item(key))));
+        }
+        self.index += 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+/// A tuple written as space-separated values in one string, which have been split into their own tokens
+struct SpaceSeparated<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    remaining: usize,
+}
+
+impl<'a, 'de> SpaceSeparated<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, len: usize) -> Self {
+        Self {
+            de,
+            remaining: len,
+        }
+    }
+}
+
+impl<'de, 'a> SeqAccess<'de> for SpaceSeparated<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+        where
+            T: DeserializeSeed<'de>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
         seed.deserialize(&mut *self.de).map(Some)
     }
 
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+/// The entries of a group where a key repeats, as one-entry maps
+struct SingleEntries<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'de, 'a> SeqAccess<'de> for SingleEntries<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+        where
+            T: DeserializeSeed<'de>,
+    {
+        if let Token::GroupEnd = self.de.peek_token()? {
+            return Ok(None);
+        }
+        seed.deserialize(SingleEntry { de: &mut *self.de, done: false }).map(Some)
+    }
+}
+
+/// One entry of a group, as a map of its own
+struct SingleEntry<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    done: bool,
+}
+
+impl<'de, 'a> de::Deserializer<'de> for SingleEntry<'a, 'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de, 'a> MapAccess<'de> for SingleEntry<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where
+            K: DeserializeSeed<'de>,
+    {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+        seed.deserialize(MapKey { de: &mut *self.de }).map(Some)
+    }
+
     fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
         where
             V: DeserializeSeed<'de>,
     {
-        // Deserialize a map value.
         seed.deserialize(&mut *self.de)
     }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(if self.done { 0 } else { 1 })
+    }
+}
+
+/// The values of a key that was repeated in a group, the first where it is and the rest taken from later in the group
+struct RepeatedKey<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    key: Cow<'de, str>,
+    first: bool,
+    rest: Option<VecDeque<Vec<(Token<'de>, usize)>>>,
+}
+
+impl<'a, 'de> RepeatedKey<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, key: Cow<'de, str>) -> Self {
+        Self {
+            de,
+            key,
+            first: true,
+            rest: None,
+        }
+    }
+}
+
+impl<'de, 'a> SeqAccess<'de> for RepeatedKey<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+        where
+            T: DeserializeSeed<'de>,
+    {
+        if self.first {
+            self.first = false;
+            return seed.deserialize(&mut *self.de).map(Some);
+        }
+        // the rest are only looked for once the first has been read, so the group is left alone until then
+        if self.rest.is_none() {
+            self.rest = Some(self.de.take_repeats(&self.key)?);
+        }
+        let tokens = match self.rest.as_mut().and_then(VecDeque::pop_front) {
+            Some(tokens) => tokens,
+            None => return Ok(None),
+        };
+        for token in tokens.into_iter().rev() {
+            self.de.parsed_input.push_front(token);
+        }
+        // every value after the first still has its copy of the key in front of it
+        self.de.next_token_item()?;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.rest.as_ref().map(VecDeque::len)
+    }
 }
 
 #[cfg(test)]
@@ -319,4 +1353,306 @@ mod tests {
         };
         assert_eq!(expected, from_str(j).unwrap());
     }
+
+    #[test]
+    fn test_empty_strings() {
+        use std::collections::HashMap;
+
+        let data: HashMap<String, String> = from_str("{ \"a\" \"\" \"\" \"b\"\n\t\"c\"\t\"\"\n}").unwrap();
+        assert_eq!((data["a"].as_str(), data[""].as_str(), data["c"].as_str()), ("", "b", ""));
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        use std::collections::HashMap;
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            name: String,
+            solid: Vec<u8>,
+        }
+
+        let j = concat!(
+            "\"Test\"\n",
+            "{\n",
+            "\t\"name\"\t\"first\"\n",
+            "\t\"solid\"\t\"1\"\n",
+            "\t\"name\"\t\"second\"\n",
+            "\t\"solid\"\t\"2\"\n",
+            "}"
+        );
+        let parse_map = |policy| {
+            let mut deserializer = Deserializer::from_str(&j[7..]).duplicate_keys(policy);
+            let map = HashMap::<String, String>::deserialize(&mut deserializer)?;
+            deserializer.end()?;
+            Ok::<_, Error>(map)
+        };
+        assert_eq!(parse_map(DuplicateKeys::FirstWins).unwrap()["name"], "first");
+        assert_eq!(parse_map(DuplicateKeys::LastWins).unwrap()["name"], "second");
+        assert_eq!(parse_map(DuplicateKeys::Error), Err(Error::DuplicateKey {
+            key: "name".to_string(),
+            first: Position { line: 2, column: 2 },
+            second: Position { line: 4, column: 2 },
+        }));
+
+        let mut deserializer = Deserializer::from_str(j).duplicate_keys(DuplicateKeys::Collect);
+        let result = Test::deserialize(&mut deserializer);
+        assert_eq!(result, Err(Error::DuplicateKey {
+            key: "name".to_string(),
+            first: Position { line: 3, column: 2 },
+            second: Position { line: 5, column: 2 },
+        }));
+
+        let j = "\"WithOther\" { \"name\" \"x\" \"solid\" \"1\" \"other\" { \"solid\" \"9\" } \"solid\" \"2\" }";
+        let mut deserializer = Deserializer::from_str(j).duplicate_keys(DuplicateKeys::Collect);
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Other {
+            solid: Vec<u8>,
+        }
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct WithOther {
+            name: String,
+            solid: Vec<u8>,
+            other: Other,
+        }
+        let expected = WithOther {
+            name: "x".to_string(),
+            solid: vec![1, 2],
+            other: Other { solid: vec![9] },
+        };
+        assert_eq!(WithOther::deserialize(&mut deserializer).unwrap(), expected);
+        deserializer.end().unwrap();
+
+        // maps see every entry in order, unless they ask for a key's values as a sequence
+        let j = "{ \"a\" \"1\" \"b\" \"2\" \"a\" \"3\" }";
+        let mut deserializer = Deserializer::from_str(j).duplicate_keys(DuplicateKeys::Collect);
+        let map = crate::Map::<String, String>::deserialize(&mut deserializer).unwrap();
+        let entries = map.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>();
+        assert_eq!(entries, ["a=1", "b=2", "a=3"]);
+        let mut deserializer = Deserializer::from_str(j).duplicate_keys(DuplicateKeys::Collect);
+        assert_eq!(HashMap::<String, String>::deserialize(&mut deserializer).unwrap()["a"], "3");
+        let mut deserializer = Deserializer::from_str(j).duplicate_keys(DuplicateKeys::Collect);
+        let map = HashMap::<String, Vec<u8>>::deserialize(&mut deserializer).unwrap();
+        assert_eq!((&map["a"][..], &map["b"][..]), (&[1, 3][..], &[2][..]));
+    }
+
+    #[test]
+    fn test_lenient_bools() {
+        use std::collections::HashMap;
+
+        let j = "{ \"a\" \"TRUE\" \"b\" \"no\" \"c\" \"\" \"d\" \"1\" }";
+        assert!(from_str::<HashMap<String, bool>>(j).is_err());
+
+        let mut deserializer = Deserializer::from_str(j).lenient_bools(true);
+        let data = HashMap::<String, bool>::deserialize(&mut deserializer).unwrap();
+        assert_eq!((data["a"], data["b"], data["c"], data["d"]), (true, false, false, true));
+
+        let mut deserializer = Deserializer::from_str("{ \"a\" \"maybe\" }").lenient_bools(true);
+        assert!(HashMap::<String, bool>::deserialize(&mut deserializer).is_err());
+    }
+
+    #[test]
+    fn test_lenient_numbers() {
+        assert_eq!(atoi(" 5"), 5);
+        assert_eq!(atoi("5.0"), 5);
+        assert_eq!(atoi("-0x10"), -16);
+        assert_eq!(atoi(""), 0);
+        assert_eq!(atof("1e3"), 1000.0);
+        assert_eq!(atof(" -2.5e"), -2.5);
+        assert_eq!(atof("0x10"), 16.0);
+        assert_eq!(atof("fast"), 0.0);
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Inner {
+            volume: f32,
+            level: u8,
+        }
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            rate: i32,
+            inner: Inner,
+        }
+
+        let j = "\"Test\" { \"rate\" \"128000\" \"inner\" { \"volume\" \".5 \" \"level\" \"300\" } }";
+        assert!(from_str::<Test>(j).is_err());
+
+        let mut deserializer = Deserializer::from_str(j).lenient_numbers(true);
+        let data = Test::deserialize(&mut deserializer).unwrap();
+        assert_eq!(data, Test { rate: 128000, inner: Inner { volume: 0.5, level: 255 } });
+        assert_eq!(deserializer.coercions(), &[
+            Coercion { path: "inner/volume".to_string(), text: ".5 ".to_string(), position: Position { line: 1, column: 45 } },
+            Coercion { path: "inner/level".to_string(), text: "300".to_string(), position: Position { line: 1, column: 59 } },
+        ]);
+    }
+
+    #[test]
+    fn test_unit_and_tuples() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Marker;
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Pair(u8, String);
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            unit: (),
+            marker: Marker,
+            pair: Pair,
+            spaced: Pair,
+            tuple: (f32, bool),
+        }
+
+        let j = concat!(
+            "\"Test\"\n",
+            "{\n",
+            "\t\"unit\"\t\"\"\n",
+            "\t\"marker\" { }\n",
+            "\t\"pair\" { \"0\" \"7\" \"1\" \"seven\" }\n",
+            "\t\"spaced\"\t\"8 eight\"\n",
+            "\t\"tuple\"\t\"0.5 1\"\n",
+            "}"
+        );
+        let expected = Test {
+            unit: (),
+            marker: Marker,
+            pair: Pair(7, "seven".to_string()),
+            spaced: Pair(8, "eight".to_string()),
+            tuple: (0.5, true),
+        };
+        assert_eq!(from_str::<Test>(j).unwrap(), expected);
+        assert_eq!(from_str::<Marker>("\"Marker\" { }").unwrap(), Marker);
+        assert!(from_str::<Pair>("\"Pair\" \"1 2 3\"").is_err());
+        assert!(from_str::<Pair>("\"Pair\" { \"7\" \"1\" \"x\" \"b\" }").is_err());
+        assert!(from_str::<Pair>("\"Pair\" { \"1\" \"seven\" \"0\" \"7\" }").is_err());
+    }
+
+    #[test]
+    fn test_borrowed_strings() {
+        use std::collections::HashMap;
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test<'a> {
+            plain: &'a str,
+            #[serde(borrow)]
+            cow: Cow<'a, str>,
+            #[serde(borrow)]
+            escaped: Cow<'a, str>,
+        }
+
+        let j = r#""Test" { "plain" "hello" "cow" "there" "escaped" "a\nb" }"#;
+        let test: Test = from_str(j).unwrap();
+        assert_eq!(test.plain, "hello");
+        assert!(matches!(test.cow, Cow::Borrowed("there")));
+        assert!(matches!(test.escaped, Cow::Owned(ref data) if data == "a\nb"));
+
+        let j = r#"{ "key" "value" }"#;
+        let map: HashMap<&str, &str> = from_str(j).unwrap();
+        assert_eq!(map["key"].as_ptr(), j[9..].as_ptr());
+        assert!(from_str::<HashMap<&str, &str>>(r#"{ "key" "a\"b" }"#).is_err());
+    }
+
+    #[test]
+    fn test_map_keys() {
+        use std::collections::HashMap;
+
+        #[derive(Deserialize, PartialEq, Eq, Hash, Debug)]
+        enum Branch { Public, Beta }
+
+        let apps: HashMap<u32, String> = from_str(r#"{ "440" "Team Fortress 2" "620" "Portal 2" }"#).unwrap();
+        assert_eq!(apps[&620], "Portal 2");
+
+        let branches: HashMap<Branch, bool> = from_str(r#"{ "Public" "1" "Beta" "0" }"#).unwrap();
+        assert!(branches[&Branch::Public]);
+
+        #[derive(Deserialize, PartialEq, Eq, Hash, Debug)]
+        enum Key { Unit, Newtype(u8), Tuple(u8, u8), Struct { a: u8 } }
+        assert_eq!(from_str::<HashMap<Key, u8>>(r#"{ "Unit" "1" }"#).unwrap()[&Key::Unit], 1);
+        assert_eq!(from_str::<HashMap<Key, u8>>(r#"{ "Newtype" "1" }"#), Err(Error::UnsupportedKey("newtype_variant")));
+        assert_eq!(from_str::<HashMap<Key, u8>>(r#"{ "Tuple" "1" }"#), Err(Error::UnsupportedKey("tuple_variant")));
+        assert_eq!(from_str::<HashMap<Key, u8>>(r#"{ "Struct" "1" }"#), Err(Error::UnsupportedKey("struct_variant")));
+
+        assert_eq!(from_str::<HashMap<(u8, u8), String>>(r#"{ "1 2" "nope" }"#), Err(Error::UnsupportedKey("tuple")));
+        assert!(from_str::<HashMap<u32, String>>(r#"{ "Public" "1" }"#).is_err());
+    }
+
+    #[test]
+    fn test_comments() {
+        use std::collections::HashMap;
+
+        let j = "// settings\n{\n\t\"a\"\t\"1\" // first\n\t// \"b\" \"2\"\n}\n// trailing";
+        let map: HashMap<String, u8> = from_str(j).unwrap();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map["a"], 1);
+    }
+
+    #[test]
+    fn test_any_and_ignored() {
+        use crate::{vdf, Value};
+
+        let j = "#base \"base.vdf\"\n\"Root\"\n{\n\t\"a\"\t\"1\"\n\t\"a\"\t{ \"x\" \"y\" }\n\t\"b\"\t\"2\"\n}";
+        let value: Value = from_str(j).unwrap();
+        assert_eq!(value, vdf! { "#base" "base.vdf" "Root" { "a" "1" "a" { "x" "y" } "b" "2" } });
+        assert_eq!(from_str::<Value>("\"lone\""), Ok(Value::from("lone")));
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Root {
+            b: u8,
+        }
+        let j = "\"Root\"\n{\n\t\"a\"\t\"1\"\n\t\"a\"\t{ \"x\" { \"deep\" \"y\" } \"x\" \"z\" }\n\t\"b\"\t\"2\"\n\t\"c\"\t{ }\n}";
+        assert_eq!(from_str::<Root>(j), Ok(Root { b: 2 }));
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        #[serde(deny_unknown_fields)]
+        struct Strict {
+            b: u8,
+        }
+        assert_eq!(from_str::<Strict>("\"Strict\" { \"b\" \"2\" }"), Ok(Strict { b: 2 }));
+        assert_eq!(
+            from_str::<Strict>("\"Strict\" { \"a\" { \"x\" \"y\" } \"b\" \"2\" }"),
+            Err(Error::Message("unknown field `a`, expected `b`".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_any_collect() {
+        use crate::Value;
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Outer {
+            solid: Vec<Value>,
+        }
+
+        // the solids' own repeated keys are interleaved, and come through in order
+        let j = "\"Outer\" { \"solid\" { \"side\" \"1\" \"id\" \"2\" \"side\" \"3\" } \"solid\" { \"side\" \"4\" } }";
+        let mut deserializer = Deserializer::from_str(j).duplicate_keys(DuplicateKeys::Collect);
+        let outer = Outer::deserialize(&mut deserializer).unwrap();
+        let solids = outer.solid.iter().map(Value::to_string).collect::<Vec<_>>();
+        assert_eq!(solids, ["\"side\"\t\"1\"\n\"id\"\t\"2\"\n\"side\"\t\"3\"", "\"side\"\t\"4\""]);
+
+        let mut deserializer = Deserializer::from_str(j).duplicate_keys(DuplicateKeys::Collect);
+        let value = Value::deserialize(&mut deserializer).unwrap();
+        assert_eq!(value, j.parse::<Value>().unwrap());
+    }
+
+    #[test]
+    fn test_any_nested_duplicates() {
+        use crate::Value;
+
+        // keys repeat in every other group, and in the innermost one
+        let mut j = String::new();
+        for level in 0..200 {
+            if level % 2 == 1 {
+                j.push_str("\"x\" \"1\" \"x\" \"2\" ");
+            }
+            j.push_str("\"a\" { ");
+        }
+        j.push_str("\"y\" \"3\" \"y\" \"4\"");
+        j.push_str(&" }".repeat(200));
+        let value: Value = from_str(&j).unwrap();
+        assert_eq!(value, j.parse::<Value>().unwrap());
+    }
 }
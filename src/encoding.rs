@@ -0,0 +1,225 @@
+//! Text encodings that VDF files are found in, since not everything Valve ships is UTF-8
+
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::error::{Error, Result};
+
+/// A text encoding for a whole VDF file
+///
+/// [`from_bytes`](fn.from_bytes.html) works out which one a file is in with [`detect`](#method.detect).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// UTF-8, with or without a byte order mark
+    Utf8,
+    /// UTF-16, little-endian, which is what localization files in `resource/` usually are
+    Utf16Le,
+    /// UTF-16, big-endian
+    Utf16Be,
+    /// Windows-1252, the Western European code page that older Source mods tend to be saved in
+    Windows1252,
+    /// ISO-8859-1, where each byte is the Unicode character with the same number
+    Latin1,
+}
+
+/// The characters for bytes 0x80 to 0x9F in Windows-1252
+///
+/// The five bytes the code page doesn't use stand for the control characters with the same number,
+/// like Windows does, so any byte can be read and written back.
+const WINDOWS_1252: [u16; 32] = [
+    0x20AC, 0x0081, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6, 0x2030, 0x0160, 0x2039, 0x0152, 0x008D, 0x017D, 0x008F,
+    0x0090, 0x2018, 0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014, 0x02DC, 0x2122, 0x0161, 0x203A, 0x0153, 0x009D, 0x017E, 0x0178,
+];
+
+impl Encoding {
+    /// Works out which encoding `bytes` are in from their byte order mark, if they have one
+    ///
+    /// This only picks between UTF-8 and UTF-16, since there's no telling single-byte code pages apart.
+    ///
+    /// Without one, text that starts with an ASCII character and a zero byte is taken to be UTF-16LE,
+    /// text that starts with a zero byte and an ASCII character is UTF-16BE, and anything else is UTF-8.
+    /// VDF always starts with ASCII, like a quote or a comment, so that's enough to tell them apart.
+    ///
+    /// ```
+    /// use vdf_serde::Encoding;
+    ///
+    /// assert_eq!(Encoding::detect(b"\xff\xfe\"\0a\0\"\0"), Encoding::Utf16Le);
+    /// assert_eq!(Encoding::detect(b"\0\"\0a\0\""), Encoding::Utf16Be);
+    /// assert_eq!(Encoding::detect(b"\xef\xbb\xbf\"a\""), Encoding::Utf8);
+    /// ```
+    pub fn detect(bytes: &[u8]) -> Encoding {
+        match bytes {
+            [0xFF, 0xFE, ..] => Encoding::Utf16Le,
+            [0xFE, 0xFF, ..] => Encoding::Utf16Be,
+            [0xEF, 0xBB, 0xBF, ..] => Encoding::Utf8,
+            [first, 0, ..] if first.is_ascii() && *first != 0 => Encoding::Utf16Le,
+            [0, second, ..] if second.is_ascii() && *second != 0 => Encoding::Utf16Be,
+            _ => Encoding::Utf8,
+        }
+    }
+
+    /// Returns the byte order mark for this encoding, which is empty for single-byte code pages
+    pub fn bom(self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8 => &[0xEF, 0xBB, 0xBF],
+            Encoding::Utf16Le => &[0xFF, 0xFE],
+            Encoding::Utf16Be => &[0xFE, 0xFF],
+            Encoding::Windows1252 | Encoding::Latin1 => &[],
+        }
+    }
+
+    /// Decodes `bytes` from this encoding, skipping its byte order mark if they start with one
+    ///
+    /// # Errors
+    ///
+    /// If `bytes` aren't valid in this encoding, an [`Error::Encoding`](enum.Error.html#variant.Encoding)
+    /// with the offset of the first invalid byte will be returned.
+    pub fn decode(self, bytes: &[u8]) -> Result<String> {
+        let start = if bytes.starts_with(self.bom()) { self.bom().len() } else { 0 };
+        let invalid = |offset| Error::Encoding { encoding: self, offset };
+        match self {
+            Encoding::Utf8 => match std::str::from_utf8(&bytes[start..]) {
+                Ok(text) => Ok(text.to_string()),
+                Err(err) => Err(invalid(start + err.valid_up_to())),
+            },
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let pairs = bytes[start..].chunks_exact(2);
+                let odd_byte = !pairs.remainder().is_empty();
+                let units = pairs.map(|pair| match self {
+                    Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                    _ => u16::from_be_bytes([pair[0], pair[1]]),
+                });
+                let mut text = String::with_capacity(bytes.len() / 2);
+                let mut offset = start;
+                for c in char::decode_utf16(units) {
+                    let c = c.map_err(|_| invalid(offset))?;
+                    offset += c.len_utf16() * 2;
+                    text.push(c);
+                }
+                if odd_byte {
+                    return Err(invalid(bytes.len() - 1));
+                }
+                Ok(text)
+            }
+            Encoding::Windows1252 => Ok(bytes
+                .iter()
+                .map(|&byte| match byte {
+                    0x80..=0x9F => char::from_u32(WINDOWS_1252[usize::from(byte - 0x80)].into()).expect("the table only has characters"),
+                    _ => char::from(byte),
+                })
+                .collect()),
+            Encoding::Latin1 => Ok(bytes.iter().map(|&byte| char::from(byte)).collect()),
+        }
+    }
+
+    /// Encodes `text` in this encoding, starting with a byte order mark for UTF-16 but not for UTF-8
+    ///
+    /// # Errors
+    ///
+    /// If `text` has a character that this encoding doesn't have, an
+    /// [`Error::Unmappable`](enum.Error.html#variant.Unmappable) will be returned with the offset in the
+    /// encoded bytes that it would have gone at, which is also how many characters come before it.
+    pub fn encode(self, text: &str) -> Result<Vec<u8>> {
+        let unmappable = |offset| Error::Unmappable { encoding: self, offset };
+        match self {
+            Encoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let mut bytes = self.bom().to_vec();
+                for unit in text.encode_utf16() {
+                    bytes.extend_from_slice(&match self {
+                        Encoding::Utf16Le => unit.to_le_bytes(),
+                        _ => unit.to_be_bytes(),
+                    });
+                }
+                Ok(bytes)
+            }
+            // both code pages are one byte per character, so the offset in the output is the character's index
+            Encoding::Windows1252 => text
+                .chars()
+                .enumerate()
+                .map(|(offset, c)| match u32::from(c) {
+                    code @ (0..=0x7F | 0xA0..=0xFF) => Ok(code as u8),
+                    code => WINDOWS_1252
+                        .iter()
+                        .position(|&mapped| u32::from(mapped) == code)
+                        .map(|i| 0x80 + i as u8)
+                        .ok_or_else(|| unmappable(offset)),
+                })
+                .collect(),
+            Encoding::Latin1 => text.chars().enumerate().map(|(offset, c)| u8::try_from(c).map_err(|_| unmappable(offset))).collect(),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Windows1252 => "Windows-1252",
+            Encoding::Latin1 => "Latin-1",
+        })
+    }
+}
+
+/// Decodes VDF text in whichever encoding [`Encoding::detect`] finds it's in
+pub(crate) fn decode(bytes: &[u8]) -> Result<String> {
+    Encoding::detect(bytes).decode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let text = "\"lang\" { \"Tokens\" { \"hi\" \"h\u{e9}llo \u{1f600}\" } }";
+        let utf16 = |bom: bool, le: bool| {
+            let mut bytes = if bom { vec![0xFE, 0xFF] } else { vec![] };
+            for unit in text.encode_utf16() {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+            if le {
+                bytes.chunks_mut(2).for_each(|pair| pair.swap(0, 1));
+            }
+            bytes
+        };
+        for (bytes, encoding) in [
+            (text.as_bytes().to_vec(), Encoding::Utf8),
+            ([&[0xEF, 0xBB, 0xBF], text.as_bytes()].concat(), Encoding::Utf8),
+            (utf16(true, true), Encoding::Utf16Le),
+            (utf16(false, true), Encoding::Utf16Le),
+            (utf16(true, false), Encoding::Utf16Be),
+            (utf16(false, false), Encoding::Utf16Be),
+        ] {
+            assert_eq!(Encoding::detect(&bytes), encoding);
+            assert_eq!(decode(&bytes).as_deref(), Ok(text));
+        }
+
+        assert_eq!(decode(b"\"a\" \"\xff\""), Err(Error::Encoding { encoding: Encoding::Utf8, offset: 5 }));
+        // a lone high surrogate, after the BOM and one character
+        assert_eq!(decode(b"\xff\xfe\"\0\x00\xd8a\0"), Err(Error::Encoding { encoding: Encoding::Utf16Le, offset: 4 }));
+        assert_eq!(decode(b"\xff\xfe\"\0a"), Err(Error::Encoding { encoding: Encoding::Utf16Le, offset: 4 }));
+    }
+
+    #[test]
+    fn test_code_pages() {
+        let text = "\"caf\u{e9}\" \"\u{20ac}5 \u{2013} \u{153}uvre\"";
+        let windows_1252 = b"\"caf\xe9\" \"\x805 \x96 \x9cuvre\"";
+        assert_eq!(Encoding::Windows1252.encode(text).unwrap(), windows_1252);
+        assert_eq!(Encoding::Windows1252.decode(windows_1252).unwrap(), text);
+        // the bytes Windows-1252 doesn't use come through as the control characters with the same number
+        let unused = b"\x81\x8d\x8f\x90\x9d";
+        assert_eq!(Encoding::Windows1252.decode(unused).unwrap(), "\u{81}\u{8d}\u{8f}\u{90}\u{9d}");
+        assert_eq!(Encoding::Windows1252.encode("\u{81}\u{8d}\u{8f}\u{90}\u{9d}").unwrap(), unused);
+        assert_eq!(Encoding::Windows1252.encode("\u{80}"), Err(Error::Unmappable { encoding: Encoding::Windows1252, offset: 0 }));
+        // the offset is in the encoded bytes, where the two-byte "\u{e9}" only takes one
+        assert_eq!(Encoding::Windows1252.encode("a\u{e9}\u{3b1}"), Err(Error::Unmappable { encoding: Encoding::Windows1252, offset: 2 }));
+
+        assert_eq!(Encoding::Latin1.decode(b"caf\xe9 \x80").unwrap(), "caf\u{e9} \u{80}");
+        assert_eq!(Encoding::Latin1.encode("caf\u{e9}").unwrap(), b"caf\xe9");
+        assert_eq!(Encoding::Latin1.encode(text), Err(Error::Unmappable { encoding: Encoding::Latin1, offset: 8 }));
+        assert_eq!(Encoding::Utf16Le.encode("a").unwrap(), b"\xff\xfea\0");
+    }
+}
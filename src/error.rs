@@ -4,6 +4,8 @@ use std::fmt::{self, Display};
 
 use serde::{de, ser};
 
+use crate::encoding::Encoding;
+
 /// Alias for a `Result` with the error type `vdf_serde::Error`
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -17,6 +19,9 @@ pub enum Error {
     /// Unsupported Serde data type
     UnsupportedType(&'static str),
 
+    /// Unsupported Serde data type used as a map key, since keys have to be strings
+    UnsupportedKey(&'static str),
+
     /// EOF too early
     EarlyEOF,
 
@@ -31,7 +36,80 @@ pub enum Error {
     Expected(&'static str, String),
 
     /// Failed to parse from a string to some other type
-    StringParse(String)
+    StringParse(String),
+
+    /// A key appeared more than once in the same group
+    DuplicateKey {
+        /// The repeated key
+        key: String,
+        /// Where the key was first seen
+        first: Position,
+        /// Where the key was seen again
+        second: Position,
+    },
+
+    /// Reading the input or writing the output failed
+    /// (This is the message from an io::Error, so that Error can stay Clone and PartialEq)
+    Io(String),
+
+    /// A patch didn't match the value it was applied to
+    PatchConflict {
+        /// Where the patch didn't match
+        path: String,
+        /// What didn't match
+        reason: String,
+    },
+
+    /// Bytes that aren't valid text in the encoding they were read as
+    Encoding {
+        /// The encoding
+        encoding: Encoding,
+        /// Where the first invalid byte is
+        offset: usize,
+    },
+
+    /// A character that can't be written in the encoding it was being written in
+    Unmappable {
+        /// The encoding
+        encoding: Encoding,
+        /// Where the character would have gone in the encoded bytes
+        offset: usize,
+    },
+
+    /// Something went wrong with a particular file
+    File {
+        /// The file's path
+        path: String,
+        /// What went wrong
+        error: Box<Error>,
+    },
+}
+
+/// A line and column in VDF text, both starting from 1
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position {
+    /// Line number
+    pub line: usize,
+    /// Column number, counted in characters
+    pub column: usize,
+}
+
+impl Position {
+    /// Finds the position of the byte `offset` into `input`
+    pub fn from_offset(input: &str, offset: usize) -> Self {
+        let before = &input[..offset.min(input.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Position {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}:{}", self.line, self.column)
+    }
 }
 
 impl ser::Error for Error {
@@ -51,11 +129,19 @@ impl Display for Error {
         match self {
             Error::Message(msg) => formatter.write_str(msg),
             Error::UnsupportedType(r#type) => write!(formatter, "unsupported Serde data type {} used", r#type),
+            Error::UnsupportedKey(r#type) => write!(formatter, "unsupported Serde data type {} used as a map key", r#type),
             Error::EarlyEOF => formatter.write_str("input ended early"),
             Error::LateEOF => formatter.write_str("input ended late"),
             Error::Tokenize(err) => formatter.write_str(err),
             Error::Expected(wanted, got) => write!(formatter, "expected {}, got {}", wanted, got),
             Error::StringParse(err) => formatter.write_str(err),
+            Error::DuplicateKey { key, first, second } =>
+                write!(formatter, "duplicate key {:?} at {} (first seen at {})", key, second, first),
+            Error::Io(err) => write!(formatter, "I/O failed: {}", err),
+            Error::PatchConflict { path, reason } => write!(formatter, "patch conflict at {:?}: {}", path, reason),
+            Error::Encoding { encoding, offset } => write!(formatter, "invalid {} at byte {}", encoding, offset),
+            Error::Unmappable { encoding, offset } => write!(formatter, "the character that would go at byte {} can't be written in {}", offset, encoding),
+            Error::File { path, error } => write!(formatter, "{}: {}", path, error),
         }
     }
 }
@@ -0,0 +1,407 @@
+//! Convert between VDF and JSON, keeping repeated keys and the order of entries
+//!
+//! VDF strings are JSON strings, and a group is a JSON object with its entries in order,
+//! unless a key shows up more than once in it. Then the group is an array of one-entry objects instead,
+//! so that every entry and the order they're in survives:
+//!
+//! ```text
+//! "Root"                      {
+//! {                             "Root": [
+//!     "name"  "x"                 { "name": "x" },
+//!     "tag"   "a"      <=>        { "tag": "a" },
+//!     "tag"   "b"                 { "tag": "b" }
+//! }                             ]
+//!                             }
+//! ```
+//!
+//! A whole document is the object of its top-level pairs, so the root name is just the one key in it,
+//! and directives like `#base "file.vdf"` are entries with keys starting with `#`, like they are in a
+//! [`Value`](../enum.Value.html). Arrays don't mean anything else, so going back the other way is never ambiguous.
+//! When reading JSON, numbers become strings as they're written, `true` and `false` become `"1"` and `"0"`,
+//! and `null` becomes `""`.
+//!
+//! A [`Value`](../enum.Value.html) has no conditions, so to keep them, convert a
+//! [`Document`](../struct.Document.html) with [`document_to_string`](fn.document_to_string.html) and
+//! [`document_from_str`](fn.document_from_str.html). A group with a condition anywhere in it is an array too,
+//! and an entry with a condition has it as a second member, after the entry itself:
+//!
+//! ```text
+//! "Root"                            {
+//! {                                   "Root": [
+//!     "font"  "Arial"  [$WIN32] <=>     { "font": "Arial", "[$WIN32]": true },
+//!     "font"  "Helvetica"               { "font": "Helvetica" }
+//! }                                   ]
+//!                                   }
+//! ```
+//!
+//! What doesn't make it into JSON either way:
+//!
+//! - comments
+//! - the layout: indentation, line breaks, and which strings were quoted
+//! - conditions, when converting a `Value`, since it never had them; [`from_str`](fn.from_str.html) reads
+//!   past condition members the same way parsing VDF into a `Value` reads past conditions
+//!
+//! The [`Deserializer`](../struct.Deserializer.html) follows the same mapping from `deserialize_any`, except
+//! for conditions, which it reads past. So VDF can be streamed straight into any other Serde format with
+//! [serde_transcode](https://crates.io/crates/serde-transcode), without building a `Value` first.
+//!
+//! ```
+//! use vdf_serde::{json, Document, Value};
+//!
+//! let value: Value = r#""Root" { "name" "x" "tag" "a" "tag" "b" }"#.parse()?;
+//! let text = json::to_string(&value)?;
+//! assert_eq!(text, r#"{"Root":[{"name":"x"},{"tag":"a"},{"tag":"b"}]}"#);
+//! assert_eq!(json::from_str(&text)?, value);
+//!
+//! let document: Document = r#""Root" { "font" "Arial" [$WIN32] "font" "Helvetica" }"#.parse()?;
+//! let text = json::document_to_string(&document)?;
+//! assert_eq!(text, r#"{"Root":[{"font":"Arial","[$WIN32]":true},{"font":"Helvetica"}]}"#);
+//! assert_eq!(json::document_from_str(&text)?.to_string(), "\"Root\"\n{\n\t\"font\"\t\"Arial\" [$WIN32]\n\t\"font\"\t\"Helvetica\"\n}");
+//! # Ok::<(), vdf_serde::Error>(())
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::error::{Error, Result};
+use crate::map::Map;
+use crate::reader::{Event, Reader};
+use crate::value::{Document, Value};
+use crate::writer::Writer;
+
+/// Writes a value as compact JSON
+pub fn to_string(value: &Value) -> Result<String> {
+    serde_json::to_string(&Json(value)).map_err(|err| Error::Message(err.to_string()))
+}
+
+/// Writes a value as indented JSON
+pub fn to_string_pretty(value: &Value) -> Result<String> {
+    serde_json::to_string_pretty(&Json(value)).map_err(|err| Error::Message(err.to_string()))
+}
+
+/// Reads a value from JSON
+///
+/// Conditions on entries are read past, and the entries are kept.
+///
+/// # Errors
+///
+/// If the JSON is invalid, or has an array anywhere but in place of a group, a
+/// [`StringParse`](../enum.Error.html#variant.StringParse) error will be returned.
+pub fn from_str(s: &str) -> Result<Value> {
+    let written: Written = serde_json::from_str(s).map_err(|err| Error::StringParse(err.to_string()))?;
+    Ok(written.into())
+}
+
+/// Writes a document as compact JSON, with its conditions
+pub fn document_to_string(document: &Document) -> Result<String> {
+    serde_json::to_string(&Written::parse(document.as_str())?).map_err(|err| Error::Message(err.to_string()))
+}
+
+/// Writes a document as indented JSON, with its conditions
+pub fn document_to_string_pretty(document: &Document) -> Result<String> {
+    serde_json::to_string_pretty(&Written::parse(document.as_str())?).map_err(|err| Error::Message(err.to_string()))
+}
+
+/// Reads a document from JSON, writing any conditions in it after their entries
+///
+/// # Errors
+///
+/// The same as [`from_str`](fn.from_str.html).
+pub fn document_from_str(s: &str) -> Result<Document> {
+    let written: Written = serde_json::from_str(s).map_err(|err| Error::StringParse(err.to_string()))?;
+    let mut writer = Writer::new(Vec::new());
+    written.write_document(&mut writer)?;
+    String::from_utf8(writer.into_inner()).expect("JSON strings are UTF-8").parse()
+}
+
+/// A value, serialized with groups with repeated keys as lists of entries
+struct Json<'a>(&'a Value);
+
+impl<'a> Serialize for Json<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::String(text) => serializer.serialize_str(text),
+            Value::Group(group) if has_duplicates(group) => {
+                let mut seq = serializer.serialize_seq(Some(group.len()))?;
+                for (key, value) in group {
+                    seq.serialize_element(&Entry(key, Json(value)))?;
+                }
+                seq.end()
+            }
+            Value::Group(group) => {
+                let mut map = serializer.serialize_map(Some(group.len()))?;
+                for (key, value) in group {
+                    map.serialize_entry(key, &Json(value))?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+/// One entry of a group, as an object of its own
+struct Entry<'a>(&'a str, Json<'a>);
+
+impl<'a> Serialize for Entry<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.0, &self.1)?;
+        map.end()
+    }
+}
+
+fn has_duplicates(group: &Map<String, Value>) -> bool {
+    let mut seen = HashSet::new();
+    group.keys().any(|key| !seen.insert(key))
+}
+
+/// A document as it's written, with the condition after each entry that has one
+enum Written {
+    String(String),
+    Group(Vec<WrittenEntry>),
+}
+
+struct WrittenEntry {
+    key: String,
+    value: Written,
+    condition: Option<String>,
+}
+
+impl Written {
+    /// Reads the entries of a document, the same way a [`Value`](../enum.Value.html) reads them
+    fn parse(text: &str) -> Result<Self> {
+        // each open group, with the key and condition it goes under in its parent
+        let mut stack = vec![(String::new(), None, Vec::new())];
+        let mut key = None;
+        // the condition of the group about to be opened, which comes between its key and its `{`
+        let mut condition = None;
+        let mut braced = false;
+        for item in Reader::new(text) {
+            let (event, _span) = item?;
+            match event {
+                Event::Key(text) => key = Some(text.into_owned()),
+                Event::Directive(text) => key = Some(format!("#{}", text)),
+                Event::Value(text) => {
+                    let key = key.take().unwrap_or_default();
+                    let entry = WrittenEntry { key, value: Written::String(text.into_owned()), condition: None };
+                    stack.last_mut().expect("the root never closes").2.push(entry);
+                }
+                Event::GroupStart if !braced && key.is_none() && stack.len() == 1 && stack[0].2.is_empty() => braced = true,
+                Event::GroupStart => stack.push((key.take().unwrap_or_default(), condition.take(), Vec::new())),
+                Event::GroupEnd if stack.len() == 1 => {}
+                Event::GroupEnd => {
+                    let (key, condition, entries) = stack.pop().expect("the reader checks nesting");
+                    let entry = WrittenEntry { key, value: Written::Group(entries), condition };
+                    stack.last_mut().expect("the root never closes").2.push(entry);
+                }
+                Event::Conditional(text) if key.is_some() => condition = Some(text.into_owned()),
+                Event::Conditional(text) => {
+                    if let Some(entry) = stack.last_mut().expect("the root never closes").2.last_mut() {
+                        entry.condition = Some(text.into_owned());
+                    }
+                }
+                Event::Comment(_) => {}
+            }
+        }
+        let (_, _, root) = stack.pop().expect("the reader checks nesting");
+        Ok(Written::Group(root))
+    }
+
+    fn write_document<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        match self {
+            Written::String(text) => writer.write_value(text),
+            Written::Group(entries) => write_entries(writer, entries),
+        }
+    }
+}
+
+fn write_entries<W: std::io::Write>(writer: &mut Writer<W>, entries: &[WrittenEntry]) -> Result<()> {
+    for entry in entries {
+        match (&entry.value, &entry.condition) {
+            (Written::String(text), None) => writer.write_pair(&entry.key, text)?,
+            (Written::String(text), Some(condition)) => writer.write_conditional_pair(&entry.key, text, condition)?,
+            (Written::Group(inner), condition) => {
+                match condition {
+                    Some(condition) => writer.begin_conditional_group(&entry.key, condition)?,
+                    None => writer.begin_group(&entry.key)?,
+                }
+                write_entries(writer, inner)?;
+                writer.end_group()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl From<Written> for Value {
+    fn from(written: Written) -> Self {
+        match written {
+            Written::String(text) => Value::String(text),
+            Written::Group(entries) => Value::Group(entries.into_iter().map(|entry| (entry.key, entry.value.into())).collect()),
+        }
+    }
+}
+
+/// A group with a condition anywhere in it is a list of entries too, so each condition can go with its entry
+impl Serialize for Written {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Written::String(text) => serializer.serialize_str(text),
+            Written::Group(entries) => {
+                let mut seen = HashSet::new();
+                if entries.iter().any(|entry| entry.condition.is_some() || !seen.insert(&entry.key)) {
+                    let mut seq = serializer.serialize_seq(Some(entries.len()))?;
+                    for entry in entries {
+                        seq.serialize_element(entry)?;
+                    }
+                    seq.end()
+                } else {
+                    let mut map = serializer.serialize_map(Some(entries.len()))?;
+                    for entry in entries {
+                        map.serialize_entry(&entry.key, &entry.value)?;
+                    }
+                    map.end()
+                }
+            }
+        }
+    }
+}
+
+/// One entry of a group, as an object of its own, with a `"[condition]": true` member after it if it has one
+impl Serialize for WrittenEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(if self.condition.is_some() { 2 } else { 1 }))?;
+        map.serialize_entry(&self.key, &self.value)?;
+        if let Some(condition) = &self.condition {
+            map.serialize_entry(&format!("[{}]", condition), &true)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Written {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct WrittenVisitor;
+
+        impl<'de> Visitor<'de> for WrittenVisitor {
+            type Value = Written;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string or a group")
+            }
+
+            fn visit_bool<E: serde::de::Error>(self, v: bool) -> std::result::Result<Written, E> {
+                Ok(Written::String(if v { "1" } else { "0" }.to_string()))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> std::result::Result<Written, E> {
+                Ok(Written::String(v.to_string()))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> std::result::Result<Written, E> {
+                Ok(Written::String(v.to_string()))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> std::result::Result<Written, E> {
+                Ok(Written::String(v.to_string()))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> std::result::Result<Written, E> {
+                Ok(Written::String(v.to_string()))
+            }
+
+            fn visit_unit<E: serde::de::Error>(self) -> std::result::Result<Written, E> {
+                Ok(Written::String(String::new()))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> std::result::Result<Written, A::Error> {
+                let mut entries = Vec::with_capacity(access.size_hint().unwrap_or(0));
+                while let Some((key, value)) = access.next_entry()? {
+                    entries.push(WrittenEntry { key, value, condition: None });
+                }
+                Ok(Written::Group(entries))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> std::result::Result<Written, A::Error> {
+                let mut entries = Vec::with_capacity(access.size_hint().unwrap_or(0));
+                while let Some(entry) = access.next_element()? {
+                    entries.push(entry);
+                }
+                Ok(Written::Group(entries))
+            }
+        }
+
+        deserializer.deserialize_any(WrittenVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for WrittenEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct EntryVisitor;
+
+        impl<'de> Visitor<'de> for EntryVisitor {
+            type Value = WrittenEntry;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an object with one entry, and maybe a \"[condition]\" after it")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> std::result::Result<WrittenEntry, A::Error> {
+                use serde::de::Error as _;
+
+                let (key, value) = access.next_entry()?.ok_or_else(|| A::Error::invalid_length(0, &self))?;
+                let condition = match access.next_key::<String>()? {
+                    Some(condition) if condition.len() >= 2 && condition.starts_with('[') && condition.ends_with(']') => {
+                        access.next_value::<IgnoredAny>()?;
+                        Some(condition[1..condition.len() - 1].to_string())
+                    }
+                    Some(other) => return Err(A::Error::custom(format!("expected a \"[condition]\" after {:?}, found {:?}", key, other))),
+                    None => None,
+                };
+                if access.next_key::<IgnoredAny>()?.is_some() {
+                    return Err(A::Error::invalid_length(3, &self));
+                }
+                Ok(WrittenEntry { key, value, condition })
+            }
+        }
+
+        deserializer.deserialize_map(EntryVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vdf;
+
+    #[test]
+    fn test_json() {
+        let value = vdf! {
+            "#base" "base.vdf"
+            "Root" { "b" "1" "a" { "x" "y" } "dup" "1" "dup" { "deep" "2" } }
+        };
+        let text = to_string(&value).unwrap();
+        assert_eq!(text, r##"{"#base":"base.vdf","Root":[{"b":"1"},{"a":{"x":"y"}},{"dup":"1"},{"dup":{"deep":"2"}}]}"##);
+        assert_eq!(from_str(&text), Ok(value));
+        assert_eq!(from_str(&to_string_pretty(&Value::from("lone")).unwrap()), Ok(Value::from("lone")));
+
+        assert_eq!(from_str(r#"{"n": 1.5, "b": true, "z": null, "e": []}"#), Ok(vdf! { "n" "1.5" "b" "1" "z" "" "e" {} }));
+        assert!(matches!(from_str(r#"{"a": ["b"]}"#), Err(Error::StringParse(_))));
+    }
+
+    #[test]
+    fn test_document_json() {
+        let document: Document = "// fonts\n\"Root\"\n{\n\t\"font\"\t\"Arial\" [$WIN32]\n\t\"size\" [!$X360]\n\t{\n\t\t\"px\"\t\"12\"\n\t}\n}".parse().unwrap();
+        let text = document_to_string(&document).unwrap();
+        assert_eq!(text, r#"{"Root":[{"font":"Arial","[$WIN32]":true},{"size":{"px":"12"},"[!$X360]":true}]}"#);
+        assert_eq!(document_from_str(&text).unwrap().as_str(), document.as_str().replace("// fonts\n", ""));
+        assert_eq!(from_str(&text).as_ref(), Ok(document.value()));
+        assert_eq!(document_to_string(&"\"a\" \"1\"".parse().unwrap()).unwrap(), r#"{"a":"1"}"#);
+
+        assert!(matches!(from_str(r#"{"a": [{"b": "1", "c": "2"}]}"#), Err(Error::StringParse(_))));
+        assert!(matches!(from_str(r#"{"a": [{"b": "1", "[$X360]": true, "[$PS3]": true}]}"#), Err(Error::StringParse(_))));
+    }
+}
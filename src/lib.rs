@@ -79,20 +79,16 @@
 //! The VDF format is rather drastically underspecified, so until I figure out a way to implement them in a way that's compatible with
 //! existing VDF files, the following types from [the Serde data model](https://serde.rs/data-model.html) are unsupported:
 //!
-//! - byte array
+//! - byte array (unless a [`ByteEncoding`](enum.ByteEncoding.html) is set)
 //! - option
-//! - unit `()`
-//! - unit_struct `struct WillNotWork;`
 //! - newtype_variant `enum Broken { Example(u8) }`
-//! - seq `Vec<T>`
-//! - tuple
-//! - tuple_struct `struct Unsupported(u8, bool, char);`
+//! - seq `Vec<T>` (except as a key repeated in a group, which is written once for each element with [`Serializer::repeated_keys`](struct.Serializer.html#method.repeated_keys) and read with [`DuplicateKeys::Collect`](enum.DuplicateKeys.html#variant.Collect))
 //! - tuple_variant `enum Bad { NotWorking(u8, bool, char) }`
 //! - struct_variant `enum Nope { NotHappening { datum: u8 } }`
 //!
 //! You might wind up needing to [implement Serialize yourself](https://serde.rs/impl-serialize.html) and
 //! [implement Deserialize yourself](https://serde.rs/impl-deserialize.html) if you use anything like this.
-//! The rest of the Serde data model works, though, although maps with non-atomic keys might be a bit of a mess.
+//! The rest of the Serde data model works, though, as long as map keys are strings, chars, integers, bools, or unit variants.
 //!
 //! ```
 //! use std::collections::HashMap as Map;
@@ -131,13 +127,47 @@
 //! assert_eq!(data, vdf_serde::from_str(&vdf_serde::to_string(&data)?)?);
 //! # Ok::<(), vdf_serde::Error>(())
 //! ```
+//!
+//! Some types don't have an obvious VDF equivalent, so they're written like this:
+//!
+//! - unit `()` is an empty string `""` (an empty group `{}` is also accepted when reading)
+//! - unit_struct `struct Marker;` is an empty group, under its name at the top level
+//! - tuple `(u8, bool)` and tuple_struct `struct Pair(u8, bool);` are groups with the keys `"0"`, `"1"`, and so on
+//!   (a single string of space-separated values like `"7 1"` is also accepted when reading)
+//!
+//! Anything that asks for any value, like [`Value`](enum.Value.html) or [serde_transcode](https://crates.io/crates/serde-transcode),
+//! gets strings as strings and groups as maps, except that a group where a key repeats comes as a sequence of one-entry maps.
+//! The [`json`](json/index.html) module, with the `json` feature, maps VDF to JSON the same way.
 #![deny(missing_docs)]
 #![doc(html_root_url = "https://docs.rs/vdf-serde/0.3.0")]
 
+mod bytes;
 mod de;
+mod encoding;
 mod error;
+#[cfg(feature = "json")]
+pub mod json;
+mod localization;
+mod macros;
+pub mod map;
+mod reader;
 mod ser;
+#[cfg(feature = "steam")]
+pub mod steam;
+mod value;
+mod vector;
+pub mod vmf;
+pub mod vmt;
+mod writer;
 
-pub use de::{from_str, Deserializer};
-pub use error::{Error, Result};
-pub use ser::{to_string, Serializer};
+pub use bytes::ByteEncoding;
+pub use de::{from_bytes, from_bytes_with_encoding, from_str, Coercion, Deserializer, DuplicateKeys};
+pub use encoding::Encoding;
+pub use error::{Error, Position, Result};
+pub use localization::Localization;
+pub use map::Map;
+pub use reader::{Event, IoReader, Reader, Span};
+pub use ser::{to_bytes_with_encoding, to_string, to_writer, Serializer};
+pub use value::{diff, from_value, to_value, Diff, DiffEntry, Document, Match, MergeChange, MergePolicy, Patch, PatchOp, Query, Value};
+pub use vector::{Color, ColorForm, Vector3, VectorForm};
+pub use writer::Writer;
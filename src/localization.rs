@@ -0,0 +1,165 @@
+//! Localization files, like `resource/portal2_english.txt`, which map tokens to text in one language
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::de::Deserializer;
+use crate::encoding::decode;
+use crate::error::Result;
+use crate::map::Map;
+
+/// The prefix that translations put in front of a token to keep the English text it was translated from
+const ORIGINAL_PREFIX: &str = "[english]";
+
+/// A localization file, with the text for each token in one language
+///
+/// Translations keep the English text each token was translated from, under the token's name with
+/// `[english]` in front, so translators can tell when it's changed. Those are kept apart in
+/// [`originals`](#structfield.originals), so [`tokens`](#structfield.tokens) only has the translated text.
+/// Token names are looked up regardless of case, like the game does. Conditions like `[$X360]` after a
+/// token aren't evaluated, so every variant of the token is kept in order, [`get`](#method.get) finds the
+/// first one, and the conditions themselves aren't written back out.
+///
+/// ```
+/// use vdf_serde::Localization;
+///
+/// let file: Vec<u8> = "\u{feff}\"lang\"
+/// {
+///     \"Language\"  \"french\"
+///     \"Tokens\"
+///     {
+///         \"Portal_Title\"            \"Portail\"
+///         \"[english]Portal_Title\"   \"Portal\"
+///     }
+/// }".encode_utf16().flat_map(u16::to_le_bytes).collect();
+/// let localization = Localization::from_bytes(&file)?;
+/// assert_eq!(localization.language, "french");
+/// assert_eq!(localization.get("portal_title"), Some("Portail"));
+/// assert_eq!(localization.original("Portal_Title"), Some("Portal"));
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "Lang", into = "Lang")]
+pub struct Localization {
+    /// The language, in lowercase English like `"english"` or `"schinese"`
+    pub language: String,
+    /// The text for each token, in the order they're in the file
+    pub tokens: Map<String, String>,
+    /// The English text each token was translated from, under the token's name without `[english]`
+    pub originals: Map<String, String>,
+}
+
+impl Localization {
+    /// Reads a localization file in any encoding that [`from_bytes`](fn.from_bytes.html) can detect,
+    /// matching its keys regardless of case
+    ///
+    /// # Errors
+    ///
+    /// The same as [`from_bytes`](fn.from_bytes.html).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Localization> {
+        let text = decode(bytes)?;
+        let mut deserializer = Deserializer::from_str(&text).case_insensitive(true);
+        let localization = Localization::deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(localization)
+    }
+
+    /// Returns the text for a token, if there is any
+    pub fn get(&self, token: &str) -> Option<&str> {
+        find(&self.tokens, token)
+    }
+
+    /// Returns the English text a token was translated from, if the file has it
+    pub fn original(&self, token: &str) -> Option<&str> {
+        find(&self.originals, token)
+    }
+}
+
+fn find<'a>(tokens: &'a Map<String, String>, token: &str) -> Option<&'a str> {
+    tokens.iter().find(|(key, _)| key.eq_ignore_ascii_case(token)).map(|(_, text)| text.as_str())
+}
+
+/// The file as it's written, with the originals mixed in with the rest of the tokens
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename = "lang", rename_all = "PascalCase")]
+struct Lang {
+    language: String,
+    tokens: Map<String, String>,
+}
+
+impl From<Lang> for Localization {
+    fn from(lang: Lang) -> Self {
+        let mut localization = Localization {
+            language: lang.language,
+            ..Localization::default()
+        };
+        for (key, text) in lang.tokens {
+            match key.get(..ORIGINAL_PREFIX.len()) {
+                Some(prefix) if prefix.eq_ignore_ascii_case(ORIGINAL_PREFIX) => {
+                    localization.originals.append(key[ORIGINAL_PREFIX.len()..].to_string(), text)
+                }
+                _ => localization.tokens.append(key, text),
+            }
+        }
+        localization
+    }
+}
+
+impl From<Localization> for Lang {
+    /// Puts each original right before its token, which is where Valve's files have them
+    fn from(localization: Localization) -> Self {
+        let mut tokens = Map::with_capacity(localization.tokens.len() + localization.originals.len());
+        let mut originals = localization.originals.into_iter().map(Some).collect::<Vec<_>>();
+        // where the originals for each token are, in order, so each token takes the next one for it
+        let mut positions = HashMap::<String, VecDeque<usize>>::new();
+        for (i, original) in originals.iter().enumerate() {
+            let (key, _) = original.as_ref().expect("nothing's been taken yet");
+            positions.entry(key.to_ascii_lowercase()).or_default().push_back(i);
+        }
+        for (key, text) in localization.tokens {
+            let position = positions.get_mut(&key.to_ascii_lowercase()).and_then(VecDeque::pop_front);
+            if let Some((original_key, original)) = position.and_then(|i| originals[i].take()) {
+                tokens.append(format!("{}{}", ORIGINAL_PREFIX, original_key), original);
+            }
+            tokens.append(key, text);
+        }
+        for (key, original) in originals.into_iter().flatten() {
+            tokens.append(format!("{}{}", ORIGINAL_PREFIX, key), original);
+        }
+        Lang {
+            language: localization.language,
+            tokens,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localization() {
+        let file = concat!(
+            "\"lang\"\n{\n",
+            "\t\"Language\"\t\"german\"\n",
+            "\t\"Tokens\"\n\t{\n",
+            "\t\t\"[english]Hello\"\t\"Hello\"\n",
+            "\t\t\"Hello\"\t\"Hallo\"\n",
+            "\t\t\"Bye\"\t\"Tsch\u{fc}ss\"\n",
+            "\t\t\"[english]Gone\"\t\"Removed\"\n",
+            "\t}\n}"
+        );
+        let localization = Localization::from_bytes(file.as_bytes()).unwrap();
+        assert_eq!(localization.tokens.keys().collect::<Vec<_>>(), ["Hello", "Bye"]);
+        assert_eq!(localization.get("BYE"), Some("Tsch\u{fc}ss"));
+        assert_eq!(localization.original("hello"), Some("Hello"));
+        assert_eq!(localization.original("Bye"), None);
+        assert_eq!(crate::to_string(&localization).unwrap(), file);
+
+        // an original goes with its token even when their cases differ
+        let file = "\"lang\"\n{\n\t\"Language\"\t\"german\"\n\t\"Tokens\"\n\t{\n\t\t\"[english]hello\"\t\"Hello\"\n\t\t\"Hello\"\t\"Hallo\"\n\t}\n}";
+        let localization = Localization::from_bytes(file.as_bytes()).unwrap();
+        assert_eq!(crate::to_string(&localization).unwrap(), file);
+    }
+}
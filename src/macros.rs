@@ -0,0 +1,56 @@
+/// Builds a [`Value`](enum.Value.html) out of VDF-looking syntax
+///
+/// The result is a group of the pairs inside the macro, just like parsing a document would give.
+/// Keys and values are string or number literals, or any Rust expression in parentheses;
+/// keys are turned into strings with `ToString`, and values with `Value::from`.
+/// A value can also be a group in braces. Commas between pairs are optional.
+///
+/// ```
+/// use vdf_serde::{vdf, Value};
+///
+/// let appid = 620;
+/// let language = "english";
+/// let value = vdf! {
+///     "AppState" {
+///         "appid" (appid),
+///         "StateFlags" 4,
+///         "UserConfig" {
+///             "language" (language)
+///         }
+///         (format!("extra{}", 1)) ""
+///     }
+/// };
+/// assert_eq!(value, r#""AppState" {
+///     "appid" "620"
+///     "StateFlags" "4"
+///     "UserConfig" { "language" "english" }
+///     "extra1" ""
+/// }"#.parse::<Value>()?);
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[macro_export]
+macro_rules! vdf {
+    ($($tt:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut group = $crate::Map::<::std::string::String, $crate::Value>::new();
+        $crate::vdf_internal!(@group group $($tt)*);
+        $crate::Value::Group(group)
+    }};
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! vdf_internal {
+    (@group $group:ident) => {};
+    (@group $group:ident , $($rest:tt)*) => {
+        $crate::vdf_internal!(@group $group $($rest)*);
+    };
+    (@group $group:ident $key:tt { $($inner:tt)* } $($rest:tt)*) => {
+        $group.append(::std::string::ToString::to_string(&$key), $crate::vdf!($($inner)*));
+        $crate::vdf_internal!(@group $group $($rest)*);
+    };
+    (@group $group:ident $key:tt $value:tt $($rest:tt)*) => {
+        $group.append(::std::string::ToString::to_string(&$key), $crate::Value::from($value));
+        $crate::vdf_internal!(@group $group $($rest)*);
+    };
+}
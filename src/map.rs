@@ -0,0 +1,324 @@
+//! An ordered map that keeps duplicate keys, like a VDF group does
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::iter::FromIterator;
+use std::marker::PhantomData;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// An ordered multimap, for groups whose keys can repeat
+///
+/// Entries stay in the order they were added, and a key can show up any number of times,
+/// so parsing a file into a `Map` and serializing it again keeps every entry where it was.
+/// Lookups are linear scans, which is plenty fast for the size of groups VDF files tend to have.
+///
+/// ```
+/// use serde::{Serialize, Deserialize};
+/// use vdf_serde::Map;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Proxies(Map<String, Map<String, String>>);
+///
+/// let vdf_data = "\"Proxies\"
+/// {
+/// \t\"Sine\"
+/// \t{
+/// \t\t\"resultVar\"\t\"$alpha\"
+/// \t}
+/// \t\"Sine\"
+/// \t{
+/// \t\t\"resultVar\"\t\"$color\"
+/// \t}
+/// }";
+/// let Proxies(proxies) = vdf_serde::from_str(vdf_data)?;
+/// assert_eq!(proxies.get_all("Sine").count(), 2);
+/// assert_eq!(proxies.get("Sine").and_then(|sine| sine.get("resultVar")).unwrap(), "$alpha");
+/// assert_eq!(vdf_serde::to_string(&Proxies(proxies))?, vdf_data);
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Map<K, V> {
+    entries: Vec<(K, V)>,
+}
+
+impl<K, V> Map<K, V> {
+    /// Creates an empty `Map`
+    pub fn new() -> Self {
+        Map { entries: Vec::new() }
+    }
+
+    /// Creates an empty `Map` with room for `capacity` entries
+    pub fn with_capacity(capacity: usize) -> Self {
+        Map { entries: Vec::with_capacity(capacity) }
+    }
+
+    /// Returns the number of entries, counting every copy of a repeated key
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over every entry in order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter { inner: self.entries.iter() }
+    }
+
+    /// Iterates over every entry in order, with mutable access to the values
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut { inner: self.entries.iter_mut() }
+    }
+
+    /// Iterates over every key in order, including repeats
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    /// Iterates over every value in order
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    /// Adds an entry at the end, even if the key is already present
+    pub fn append(&mut self, key: K, value: V) {
+        self.entries.push((key, value));
+    }
+
+    /// Removes every entry
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Keeps only the entries for which `f` returns `true`
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        self.entries.retain_mut(|(key, value)| f(key, value));
+    }
+}
+
+impl<K: Eq, V> Map<K, V> {
+    /// Returns the first value for `key`, like Valve's own `FindKey` does
+    pub fn get<Q>(&self, key: &Q) -> Option<&V> where K: Borrow<Q>, Q: ?Sized + Eq {
+        self.entries.iter().find(|(k, _)| k.borrow() == key).map(|(_, value)| value)
+    }
+
+    /// Returns the first value for `key`, mutably
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V> where K: Borrow<Q>, Q: ?Sized + Eq {
+        self.entries.iter_mut().find(|(k, _)| k.borrow() == key).map(|(_, value)| value)
+    }
+
+    /// Iterates over every value for `key`, in order
+    pub fn get_all<'a, Q>(&'a self, key: &'a Q) -> impl Iterator<Item = &'a V> + 'a where K: Borrow<Q>, Q: ?Sized + Eq {
+        self.entries.iter().filter(move |(k, _)| k.borrow() == key).map(|(_, value)| value)
+    }
+
+    /// Iterates over every value for `key`, in order, mutably
+    pub fn get_all_mut<'a, Q>(&'a mut self, key: &'a Q) -> impl Iterator<Item = &'a mut V> + 'a where K: Borrow<Q>, Q: ?Sized + Eq {
+        self.entries.iter_mut().filter(move |(k, _)| k.borrow() == key).map(|(_, value)| value)
+    }
+
+    /// Returns `true` if `key` appears at least once
+    pub fn contains_key<Q>(&self, key: &Q) -> bool where K: Borrow<Q>, Q: ?Sized + Eq {
+        self.get(key).is_some()
+    }
+
+    /// Makes `value` the only value for `key`
+    ///
+    /// If `key` is already present, the first entry keeps its place with the new value, any later
+    /// entries for it are removed, and the old first value is returned. Otherwise the entry is
+    /// added at the end.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entries.iter().position(|(k, _)| *k == key) {
+            Some(index) => {
+                let old = std::mem::replace(&mut self.entries[index].1, value);
+                let mut position = 0;
+                self.entries.retain(|(k, _)| {
+                    position += 1;
+                    position - 1 <= index || *k != key
+                });
+                Some(old)
+            }
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes every entry for `key`, returning their values in order
+    pub fn remove<Q>(&mut self, key: &Q) -> Vec<V> where K: Borrow<Q>, Q: ?Sized + Eq {
+        let mut removed = Vec::new();
+        let mut kept = Vec::with_capacity(self.entries.len());
+        for (k, value) in self.entries.drain(..) {
+            if k.borrow() == key {
+                removed.push(value);
+            } else {
+                kept.push((k, value));
+            }
+        }
+        self.entries = kept;
+        removed
+    }
+}
+
+impl<K, V> Default for Map<K, V> {
+    fn default() -> Self {
+        Map::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Map<K, V> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for Map<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Map { entries: iter.into_iter().collect() }
+    }
+}
+
+impl<K, V> Extend<(K, V)> for Map<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.entries.extend(iter);
+    }
+}
+
+impl<K, V> IntoIterator for Map<K, V> {
+    type Item = (K, V);
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Map<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut Map<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over the entries of a [`Map`](struct.Map.html)
+pub struct Iter<'a, K, V> {
+    inner: std::slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+/// A mutable iterator over the entries of a [`Map`](struct.Map.html)
+pub struct IterMut<'a, K, V> {
+    inner: std::slice::IterMut<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, value)| (&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, value)| (&*key, value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K: Serialize, V: Serialize> Serialize for Map<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Deserialize<'de> for Map<K, V> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MapVisitor<K, V>(PhantomData<(K, V)>);
+
+        impl<'de, K: Deserialize<'de>, V: Deserialize<'de>> Visitor<'de> for MapVisitor<K, V> {
+            type Value = Map<K, V>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a group")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut map = Map::with_capacity(access.size_hint().unwrap_or(0));
+                while let Some((key, value)) = access.next_entry()? {
+                    map.append(key, value);
+                }
+                Ok(map)
+            }
+        }
+
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut map = Map::new();
+        map.append("a", 1);
+        map.append("b", 2);
+        map.append("a", 3);
+        map.append("a", 4);
+        assert_eq!(map.get_all("a").copied().collect::<Vec<_>>(), vec![1, 3, 4]);
+
+        assert_eq!(map.insert("a", 5), Some(1));
+        assert_eq!(map.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(), vec![("a", 5), ("b", 2)]);
+        assert_eq!(map.insert("c", 6), None);
+
+        map.append("b", 7);
+        assert_eq!(map.remove("b"), vec![2, 7]);
+        assert_eq!(map.into_iter().collect::<Vec<_>>(), vec![("a", 5), ("c", 6)]);
+    }
+}
@@ -0,0 +1,445 @@
+//! Read VDF as a stream of events, without going through Serde
+
+use std::borrow::Cow;
+use std::io;
+
+use steamy_vdf::parser::{self as vdf_parser, Token};
+
+use crate::error::{Error, Position, Result};
+
+/// The characters the parser treats as whitespace
+const WHITESPACE: [char; 4] = [' ', '\t', '\n', '\r'];
+
+/// Where something is in the input, as byte offsets
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Span {
+    /// The offset of the first byte
+    pub start: usize,
+    /// The offset just past the last byte
+    pub end: usize,
+}
+
+impl Span {
+    /// Finds the line and column this starts at, given the input it came from
+    pub fn position(&self, input: &str) -> Position {
+        Position::from_offset(input, self.start)
+    }
+}
+
+/// Either a token from the parser, or a comment or conditional, which the parser doesn't know about
+pub(crate) enum RawToken<'a> {
+    Token(Token<'a>),
+    Comment(&'a str),
+    Conditional(&'a str),
+}
+
+/// Reads the token at the start of `input` and moves `input` past it, or returns `None` if there's nothing left
+///
+/// `input` must be a suffix of `original`, which is only used to work out spans.
+pub(crate) fn next_raw_token<'a>(original: &'a str, input: &mut &'a str) -> Result<Option<(RawToken<'a>, Span)>> {
+    let trimmed = input.trim_start_matches(WHITESPACE);
+    let start = original.len() - trimmed.len();
+    if trimmed.is_empty() {
+        *input = trimmed;
+        return Ok(None);
+    }
+    let (token, remainder) = if let Some(comment) = trimmed.strip_prefix("//") {
+        let end = comment.find('\n').unwrap_or(comment.len());
+        let text = &comment[..end];
+        (RawToken::Comment(text.trim_end_matches('\r')), &comment[end..])
+    } else if trimmed.starts_with("[$") || trimmed.starts_with("[!") {
+        let line = &trimmed[..trimmed.find('\n').unwrap_or(trimmed.len())];
+        let end = line.find(']').ok_or_else(|| Error::Tokenize("conditional with no closing ']'".to_string()))?;
+        (RawToken::Conditional(&trimmed[1..end]), &trimmed[end + 1..])
+    } else if let Some(remainder) = trimmed.strip_prefix("\"\"") {
+        // the parser can't handle an empty quoted string, so we do that one ourselves
+        (RawToken::Token(Token::Item(Cow::Borrowed(""))), remainder)
+    } else {
+        match vdf_parser::next(trimmed.as_bytes()) {
+            // since it came from `as_bytes` this is safe
+            nom::IResult::Done(remainder, token) => (RawToken::Token(token), unsafe { std::str::from_utf8_unchecked(remainder) }),
+            nom::IResult::Incomplete(_) => return Err(Error::EarlyEOF),
+            nom::IResult::Error(err) => return Err(Error::Tokenize(err.to_string())),
+        }
+    };
+    let consumed = &trimmed[..trimmed.len() - remainder.len()];
+    let end = start + consumed.trim_end_matches(WHITESPACE).len();
+    *input = remainder;
+    Ok(Some((token, Span { start, end })))
+}
+
+/// Something found while reading VDF
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Event<'a> {
+    /// The key of a key-value pair or a group
+    Key(Cow<'a, str>),
+    /// The value of a key-value pair
+    Value(Cow<'a, str>),
+    /// The `{` that starts a group
+    GroupStart,
+    /// The `}` that ends a group
+    GroupEnd,
+    /// A `//` comment, without the slashes
+    Comment(Cow<'a, str>),
+    /// A directive like `#base` or `#include`, without the `#`, whose argument comes next as a `Value`
+    Directive(Cow<'a, str>),
+    /// A condition like `[$X360]` or `[!$WIN32]`, without the brackets, which comes after the value
+    /// of the pair it applies to or between a group's key and its `{`
+    Conditional(Cow<'a, str>),
+}
+
+impl Event<'_> {
+    /// Copies any borrowed text, so the event no longer borrows from the input
+    pub fn into_owned(self) -> Event<'static> {
+        let owned = |text: Cow<str>| Cow::Owned(text.into_owned());
+        match self {
+            Event::Key(text) => Event::Key(owned(text)),
+            Event::Value(text) => Event::Value(owned(text)),
+            Event::GroupStart => Event::GroupStart,
+            Event::GroupEnd => Event::GroupEnd,
+            Event::Comment(text) => Event::Comment(owned(text)),
+            Event::Directive(text) => Event::Directive(owned(text)),
+            Event::Conditional(text) => Event::Conditional(owned(text)),
+        }
+    }
+}
+
+/// How deep into groups reading has got, and whether a value comes next, which decides what a token means
+#[derive(Clone, Copy, Debug, Default)]
+struct Nesting {
+    depth: usize,
+    expecting_value: bool,
+}
+
+impl Nesting {
+    /// Works out the event a token is, or errors if it can't come next
+    fn event<'a>(&mut self, token: RawToken<'a>) -> Result<Event<'a>> {
+        Ok(match token {
+            RawToken::Comment(text) => Event::Comment(Cow::Borrowed(text)),
+            RawToken::Conditional(text) => Event::Conditional(Cow::Borrowed(text)),
+            RawToken::Token(Token::Item(data)) if self.expecting_value => {
+                self.expecting_value = false;
+                Event::Value(data)
+            }
+            RawToken::Token(Token::Item(data)) => {
+                self.expecting_value = true;
+                Event::Key(data)
+            }
+            RawToken::Token(Token::Statement(data)) if !self.expecting_value => {
+                self.expecting_value = true;
+                Event::Directive(data)
+            }
+            RawToken::Token(Token::GroupStart) => {
+                self.expecting_value = false;
+                self.depth += 1;
+                Event::GroupStart
+            }
+            RawToken::Token(Token::GroupEnd) if self.depth > 0 && !self.expecting_value => {
+                self.depth -= 1;
+                Event::GroupEnd
+            }
+            RawToken::Token(got) => {
+                let wanted = if self.expecting_value { "Item or '{'" } else { "Item" };
+                return Err(Error::Expected(wanted, format!("{:?}", got)));
+            }
+        })
+    }
+
+    /// Errors if the input can't end here
+    fn end(&self) -> Result<()> {
+        if self.depth > 0 || self.expecting_value {
+            Err(Error::EarlyEOF)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A pull parser, which reads VDF one [`Event`](enum.Event.html) at a time
+///
+/// This only keeps track of how deeply nested it is, so beyond the input itself it takes the same
+/// amount of memory for a file of any size, and strings are borrowed from the input unless they
+/// have escapes in them. The whole input has to be loaded first, though, so for files too big
+/// for that, [`IoReader`](struct.IoReader.html) reads from an `io::BufRead` instead.
+///
+/// ```
+/// use vdf_serde::{Event, Reader};
+///
+/// let vdf_data = r#""AppState"
+/// {
+///     // which app this is
+///     "appid"    "620"
+///     "UserConfig" { "language" "english" }
+/// }"#;
+/// let events = Reader::new(vdf_data).map(|item| item.map(|(event, _span)| event)).collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(events, [
+///     Event::Key("AppState".into()),
+///     Event::GroupStart,
+///     Event::Comment(" which app this is".into()),
+///     Event::Key("appid".into()),
+///     Event::Value("620".into()),
+///     Event::Key("UserConfig".into()),
+///     Event::GroupStart,
+///     Event::Key("language".into()),
+///     Event::Value("english".into()),
+///     Event::GroupEnd,
+///     Event::GroupEnd,
+/// ]);
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Reader<'a> {
+    original: &'a str,
+    input: &'a str,
+    nesting: Nesting,
+    finished: bool,
+}
+
+impl<'a> Reader<'a> {
+    /// Creates a reader over a `&str`
+    pub fn new(input: &'a str) -> Self {
+        Reader {
+            original: input,
+            input,
+            nesting: Nesting::default(),
+            finished: false,
+        }
+    }
+
+    /// Returns how far into the input reading has got, as a byte offset
+    pub fn offset(&self) -> usize {
+        self.original.len() - self.input.len()
+    }
+
+    /// Returns how far into the input reading has got, as a line and column
+    ///
+    /// After an error, this is about where the error happened.
+    pub fn position(&self) -> Position {
+        Position::from_offset(self.original, self.original.len() - self.input.trim_start_matches(WHITESPACE).len())
+    }
+
+    /// Returns how many groups the last event is inside of
+    pub fn depth(&self) -> usize {
+        self.nesting.depth
+    }
+
+    fn read_event(&mut self) -> Result<Option<(Event<'a>, Span)>> {
+        let (token, span) = match next_raw_token(self.original, &mut self.input)? {
+            Some(found) => found,
+            None => return self.nesting.end().map(|()| None),
+        };
+        match self.nesting.event(token) {
+            Ok(event) => Ok(Some((event, span))),
+            Err(err) => {
+                // put things back where they were, so `position` points at the problem
+                self.input = &self.original[span.start..];
+                Err(err)
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Reader<'a> {
+    type Item = Result<(Event<'a>, Span)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = self.read_event();
+        if !matches!(result, Ok(Some(_))) {
+            self.finished = true;
+        }
+        result.transpose()
+    }
+}
+
+impl std::iter::FusedIterator for Reader<'_> {}
+
+/// A pull parser like [`Reader`](struct.Reader.html), but reading from an `io::BufRead` a line at a time
+///
+/// Only the line being read is kept in memory, along with any lines after it that a string or comment
+/// carries on into, so this handles files of any size. Events own their strings, since there's no
+/// input for them to borrow from once it's been read past.
+///
+/// ```
+/// use std::io::BufReader;
+/// use vdf_serde::{Event, IoReader};
+///
+/// let file = BufReader::new(&b"\"AppState\"\n{\n\t\"appid\"\t\"620\"\n}\n"[..]);
+/// let keys = IoReader::new(file)
+///     .filter_map(|item| match item {
+///         Ok((Event::Key(key), _span)) => Some(Ok(key.into_owned())),
+///         Ok(_) => None,
+///         Err(err) => Some(Err(err)),
+///     })
+///     .collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(keys, ["AppState", "appid"]);
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Along with everything [`Reader`](struct.Reader.html) can return, reading from the underlying
+/// reader can fail, including because of text that isn't UTF-8, which gives an
+/// [`Error::Io`](enum.Error.html#variant.Io).
+#[derive(Debug)]
+pub struct IoReader<R: io::BufRead> {
+    inner: R,
+    buffer: String,
+    offset: usize,
+    position: Position,
+    nesting: Nesting,
+    eof: bool,
+    finished: bool,
+}
+
+impl<R: io::BufRead> IoReader<R> {
+    /// Creates a reader over `inner`
+    pub fn new(inner: R) -> Self {
+        IoReader {
+            inner,
+            buffer: String::new(),
+            offset: 0,
+            position: Position { line: 1, column: 1 },
+            nesting: Nesting::default(),
+            eof: false,
+            finished: false,
+        }
+    }
+
+    /// Returns how far into the input reading has got, as a byte offset
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns how far into the input reading has got, as a line and column
+    ///
+    /// After an error, this is about where the error happened.
+    pub fn position(&self) -> Position {
+        let whitespace = self.buffer.len() - self.buffer.trim_start_matches(WHITESPACE).len();
+        advance(self.position, &self.buffer[..whitespace])
+    }
+
+    /// Returns how many groups the last event is inside of
+    pub fn depth(&self) -> usize {
+        self.nesting.depth
+    }
+
+    /// Gives back the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn read_event(&mut self) -> Result<Option<(Event<'static>, Span)>> {
+        loop {
+            let mut input = self.buffer.as_str();
+            let result = next_raw_token(&self.buffer, &mut input);
+            // a token that runs up to the end of what's been read so far might carry on past it
+            let complete = self.eof || match result {
+                Ok(Some(_)) => !input.is_empty(),
+                Ok(None) | Err(Error::EarlyEOF) => false,
+                Err(_) => true,
+            };
+            if !complete {
+                let read = self.inner.read_line(&mut self.buffer).map_err(|err| Error::Io(err.to_string()))?;
+                self.eof = read == 0;
+                continue;
+            }
+            let (token, span) = match result? {
+                Some(found) => found,
+                None => return self.nesting.end().map(|()| None),
+            };
+            let event = self.nesting.event(token)?.into_owned();
+            let consumed = self.buffer.len() - input.len();
+            let span = Span { start: self.offset + span.start, end: self.offset + span.end };
+            self.position = advance(self.position, &self.buffer[..consumed]);
+            self.offset += consumed;
+            self.buffer.drain(..consumed);
+            return Ok(Some((event, span)));
+        }
+    }
+}
+
+impl<R: io::BufRead> Iterator for IoReader<R> {
+    type Item = Result<(Event<'static>, Span)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let result = self.read_event();
+        if !matches!(result, Ok(Some(_))) {
+            self.finished = true;
+        }
+        result.transpose()
+    }
+}
+
+impl<R: io::BufRead> std::iter::FusedIterator for IoReader<R> {}
+
+/// Moves `position` past `text`
+fn advance(position: Position, text: &str) -> Position {
+    match text.rfind('\n') {
+        Some(i) => Position {
+            line: position.line + text.matches('\n').count(),
+            column: text[i + 1..].chars().count() + 1,
+        },
+        None => Position {
+            line: position.line,
+            column: position.column + text.chars().count(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spans_and_errors() {
+        let input = "\"a\" \"\"\n#base b.vdf\n{ k v } // done";
+        let events = Reader::new(input).collect::<Result<Vec<_>>>().unwrap();
+        let slices = events.iter().map(|(_, span)| &input[span.start..span.end]).collect::<Vec<_>>();
+        assert_eq!(slices, ["\"a\"", "\"\"", "#base", "b.vdf", "{", "k", "v", "}", "// done"]);
+        assert_eq!(events[2].0, Event::Directive("base".into()));
+        assert_eq!(events[4].1.position(input), Position { line: 3, column: 1 });
+
+        let mut reader = Reader::new("\"a\"\n{\n\t\"b\"\n}");
+        assert_eq!(reader.by_ref().last(), Some(Err(Error::Expected("Item or '{'", "GroupEnd".to_string()))));
+        assert_eq!(reader.position(), Position { line: 4, column: 1 });
+
+        let input = "\"A\" \"x\" [$X360]\n\"B\" [!$WIN32&&!$OSX] { }";
+        let events = Reader::new(input).map(|item| item.map(|(event, _span)| event)).collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(events, [
+            Event::Key("A".into()),
+            Event::Value("x".into()),
+            Event::Conditional("$X360".into()),
+            Event::Key("B".into()),
+            Event::Conditional("!$WIN32&&!$OSX".into()),
+            Event::GroupStart,
+            Event::GroupEnd,
+        ]);
+        assert!(matches!(Reader::new("a b [$X360\n").last(), Some(Err(Error::Tokenize(_)))));
+
+        assert_eq!(Reader::new("a { b c").last(), Some(Err(Error::EarlyEOF)));
+        assert!(Reader::new("a b }").last().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_io_reader() {
+        // a string and a comment that carry on past the end of their line, and nothing after the last token
+        let input = "\"a\" \"one\ntwo\" [$X360]\n#base b.vdf\n{ k\t\"\" } // d\u{f6}ne";
+        let borrowed = Reader::new(input).map(|item| item.map(|(event, span)| (event.into_owned(), span))).collect::<Result<Vec<_>>>();
+        let owned = IoReader::new(input.as_bytes()).collect::<Result<Vec<_>>>();
+        assert_eq!(owned, borrowed);
+        assert_eq!(owned.unwrap().len(), 10);
+
+        let mut reader = IoReader::new("\"a\"\n{\n\t\"b\"\n}\n".as_bytes());
+        assert_eq!(reader.by_ref().last(), Some(Err(Error::Expected("Item or '{'", "GroupEnd".to_string()))));
+        assert_eq!(reader.position(), Position { line: 4, column: 1 });
+        assert_eq!(IoReader::new("a { b c\n".as_bytes()).last(), Some(Err(Error::EarlyEOF)));
+        assert_eq!(IoReader::new("a \"b\n".as_bytes()).last(), Some(Err(Error::EarlyEOF)));
+        assert!(matches!(IoReader::new(&b"a \xff"[..]).last(), Some(Err(Error::Io(_)))));
+    }
+}
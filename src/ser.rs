@@ -1,14 +1,218 @@
 //! Serialize a Rust data structure into VDF data
 
+use std::io;
+
 use serde::{ser::{self, Impossible}, Serialize};
 
+use crate::bytes::ByteEncoding;
+use crate::encoding::Encoding;
 use crate::error::{Error, Result};
-use serde::ser::SerializeMap;
+use crate::writer::Writer;
+
+/// The name of a newtype struct whose contents are a whole document, which is written without braces
+/// around it even though it's a map, so that a type whose name isn't known ahead of time can still
+/// be written as `"name" { ... }`
+///
+/// Other serializers just see a newtype struct, and write the map inside it.
+pub(crate) const DOCUMENT: &str = "$vdf_serde::private::Document";
 
 /// A structure for serializing Rust values into VDF
-pub struct Serializer {
-    output: String,
-    indent_level: usize,
+///
+/// All the quoting and indentation is done by a [`Writer`](struct.Writer.html), so this only
+/// has to keep track of which key the next value goes under.
+pub struct Serializer<W: io::Write = Vec<u8>> {
+    writer: Writer<W>,
+    pending_key: Option<String>,
+    bools_as_text: bool,
+    byte_encoding: Option<ByteEncoding>,
+    bare_top_level: bool,
+    bare_group_open: bool,
+    repeated_keys: bool,
+}
+
+impl Serializer {
+    /// Creates a VDF serializer that writes into a new `String`
+    pub fn new() -> Self {
+        Serializer::from_writer(Vec::new())
+    }
+
+    /// Returns the VDF text that has been written
+    pub fn into_string(self) -> String {
+        String::from_utf8(self.into_inner()).expect("only strings were written")
+    }
+}
+
+impl<W: io::Write> Serializer<W> {
+    /// Creates a VDF serializer that writes into `writer`
+    pub fn from_writer(writer: W) -> Self {
+        Serializer {
+            writer: Writer::new(writer),
+            pending_key: None,
+            bools_as_text: false,
+            byte_encoding: None,
+            bare_top_level: false,
+            bare_group_open: false,
+            repeated_keys: false,
+        }
+    }
+
+    /// Sets whether bools are written as `"true"`/`"false"` instead of `"1"`/`"0"`
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use vdf_serde::Serializer;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Settings {
+    ///     fullscreen: bool,
+    /// }
+    ///
+    /// let mut serializer = Serializer::new().bools_as_text(true);
+    /// Settings { fullscreen: true }.serialize(&mut serializer)?;
+    /// assert_eq!(serializer.into_string(), "\"Settings\"\n{\n\t\"fullscreen\"\t\"true\"\n}");
+    /// # Ok::<(), vdf_serde::Error>(())
+    /// ```
+    pub fn bools_as_text(mut self, as_text: bool) -> Self {
+        self.bools_as_text = as_text;
+        self
+    }
+
+    /// Sets how byte arrays are written as strings, which is required for byte arrays to work at all
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use vdf_serde::{ByteEncoding, Serializer};
+    ///
+    /// #[derive(Serialize)]
+    /// struct Ticket {
+    ///     #[serde(with = "serde_bytes")]
+    ///     data: Vec<u8>,
+    /// }
+    ///
+    /// let mut serializer = Serializer::new().byte_encoding(ByteEncoding::Base64);
+    /// Ticket { data: b"hello".to_vec() }.serialize(&mut serializer)?;
+    /// assert_eq!(serializer.into_string(), "\"Ticket\"\n{\n\t\"data\"\t\"aGVsbG8=\"\n}");
+    /// # Ok::<(), vdf_serde::Error>(())
+    /// ```
+    pub fn byte_encoding(mut self, encoding: ByteEncoding) -> Self {
+        self.byte_encoding = Some(encoding);
+        self
+    }
+
+    /// Sets whether the top-level group is written as just its entries, with no name or braces around it
+    ///
+    /// This is how Hammer's `.vmf` files are laid out, and it matches
+    /// [`Deserializer::bare_top_level`](struct.Deserializer.html#method.bare_top_level).
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use vdf_serde::Serializer;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Cameras {
+    ///     activecamera: i32,
+    /// }
+    ///
+    /// #[derive(Serialize)]
+    /// struct Map {
+    ///     cameras: Cameras,
+    /// }
+    ///
+    /// let mut serializer = Serializer::new().bare_top_level(true);
+    /// Map { cameras: Cameras { activecamera: -1 } }.serialize(&mut serializer)?;
+    /// assert_eq!(serializer.into_string(), "\"cameras\"\n{\n\t\"activecamera\"\t\"-1\"\n}");
+    /// # Ok::<(), vdf_serde::Error>(())
+    /// ```
+    pub fn bare_top_level(mut self, bare: bool) -> Self {
+        self.bare_top_level = bare;
+        self
+    }
+
+    /// Sets whether a sequence under a key is written as that key once for each element, which is
+    /// what [`DuplicateKeys::Collect`](enum.DuplicateKeys.html#variant.Collect) reads back as a sequence
+    ///
+    /// Without this, sequences can't be written at all, since reading them back takes that setting.
+    ///
+    /// ```
+    /// use serde::Serialize;
+    /// use vdf_serde::{Deserializer, DuplicateKeys, Serializer};
+    ///
+    /// #[derive(Serialize, serde::Deserialize, PartialEq, Debug)]
+    /// struct Depot {
+    ///     manifest: Vec<u64>,
+    /// }
+    ///
+    /// let depot = Depot { manifest: vec![1, 2] };
+    /// assert!(vdf_serde::to_string(&depot).is_err());
+    /// let mut serializer = Serializer::new().repeated_keys(true);
+    /// depot.serialize(&mut serializer)?;
+    /// let text = serializer.into_string();
+    /// assert_eq!(text, "\"Depot\"\n{\n\t\"manifest\"\t\"1\"\n\t\"manifest\"\t\"2\"\n}");
+    /// let mut deserializer = Deserializer::from_str(&text).duplicate_keys(DuplicateKeys::Collect);
+    /// assert_eq!(serde::Deserialize::deserialize(&mut deserializer), Ok(depot));
+    /// # Ok::<(), vdf_serde::Error>(())
+    /// ```
+    pub fn repeated_keys(mut self, repeated: bool) -> Self {
+        self.repeated_keys = repeated;
+        self
+    }
+
+    /// Gives back the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+
+    /// Writes a string under the pending key, or on its own if there isn't one
+    fn write_scalar(&mut self, value: &str) -> Result<()> {
+        match self.pending_key.take() {
+            Some(key) => self.writer.write_pair(&key, value),
+            None => self.writer.write_value(value),
+        }
+    }
+
+    /// Opens a group under the pending key, or under `name` if this is the top level
+    fn open_group(&mut self, name: Option<&str>) -> Result<()> {
+        if self.bare_top_level && !self.bare_group_open && self.writer.depth() == 0 {
+            self.pending_key = None;
+            self.bare_group_open = true;
+            return Ok(());
+        }
+        match (self.pending_key.take(), name) {
+            (Some(key), _) => self.writer.begin_group(&key),
+            (None, Some(name)) if self.writer.depth() == 0 => self.writer.begin_group(name),
+            (None, _) if self.writer.depth() == 0 => {
+                // a top-level group with no name starts on a line of its own
+                io::Write::write_all(self.writer.get_mut(), b"\n").map_err(|err| Error::Io(err.to_string()))?;
+                self.writer.begin_unnamed_group()
+            }
+            (None, _) => self.writer.begin_unnamed_group(),
+        }
+    }
+
+    /// Closes the innermost group, which might be a bare top-level group that was never written
+    fn close_group(&mut self) -> Result<()> {
+        if self.bare_group_open && self.writer.depth() == 0 {
+            self.bare_group_open = false;
+            Ok(())
+        } else {
+            self.writer.end_group()
+        }
+    }
+}
+
+impl Default for Serializer {
+    fn default() -> Self {
+        Serializer::new()
+    }
+}
+
+fn bool_text(v: bool, as_text: bool) -> &'static str {
+    match (v, as_text) {
+        (true, false) => "1",
+        (false, false) => "0",
+        (true, true) => "true",
+        (false, true) => "false",
+    }
 }
 
 /// Serialize the given data structure as a String of VDF
@@ -18,36 +222,71 @@ pub struct Serializer {
 /// If `T` uses an unsupported Serde data type, or `T`'s `Serialize` implementation
 /// itself returns an error, an error will be returned.
 ///
-/// Notably, if `T` has a map with a non-atomic key, an error will not be returned.
-/// In these cases, this behavior is likely incompatible with other VDF parsers.
+/// Map keys have to be strings, chars, integers, bools, or unit variants (or newtypes around them),
+/// and any other key is an [`Error::UnsupportedKey`](enum.Error.html#variant.UnsupportedKey).
 pub fn to_string<T>(value: &T) -> Result<String>
     where
         T: Serialize,
 {
-    let mut serializer = Serializer {
-        output: String::new(),
-        indent_level: 0,
-    };
+    let mut serializer = Serializer::new();
     value.serialize(&mut serializer)?;
-    Ok(serializer.output)
+    Ok(serializer.into_string())
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+/// Serialize the given data structure as VDF into an IO stream
+///
+/// # Errors
+///
+/// The same as [`to_string`](fn.to_string.html), plus an [`Error::Io`](enum.Error.html#variant.Io)
+/// if writing fails.
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+    where
+        W: io::Write,
+        T: Serialize,
+{
+    let mut serializer = Serializer::from_writer(writer);
+    value.serialize(&mut serializer)?;
+    serializer.writer.flush()
+}
+
+/// Serialize the given data structure as VDF in a particular encoding
+///
+/// UTF-16 output starts with a byte order mark, since that's how Valve's tools write it, and the others don't.
+///
+/// ```
+/// use vdf_serde::Encoding;
+///
+/// let bytes = vdf_serde::to_bytes_with_encoding(&vdf_serde::vdf! { "name" "Caf\u{e9}" }, Encoding::Windows1252)?;
+/// assert_eq!(bytes, b"\n{\n\t\"name\"\t\"Caf\xe9\"\n}");
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// The same as [`to_string`](fn.to_string.html), plus an [`Error::Unmappable`](enum.Error.html#variant.Unmappable)
+/// with the offset in the encoded bytes where the first character that `encoding` doesn't have would have gone.
+pub fn to_bytes_with_encoding<T>(value: &T, encoding: Encoding) -> Result<Vec<u8>>
+    where
+        T: Serialize,
+{
+    encoding.encode(&to_string(value)?)
+}
+
+impl<'a, W: io::Write> ser::Serializer for &'a mut Serializer<W> {
     type Ok = ();
 
     type Error = Error;
 
-    type SerializeSeq = Impossible<(), Error>;
-    type SerializeTuple = Impossible<(), Error>;
-    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeSeq = RepeatedKey<'a, W>;
+    type SerializeTuple = NumberedKeys<'a, W>;
+    type SerializeTupleStruct = NumberedKeys<'a, W>;
     type SerializeTupleVariant = Impossible<(), Error>;
     type SerializeMap = Self;
     type SerializeStruct = Self;
     type SerializeStructVariant = Impossible<(), Error>;
 
     fn serialize_bool(self, v: bool) -> Result<()> {
-        self.output += if v { r#""1""# } else { r#""0""# };
-        Ok(())
+        self.write_scalar(bool_text(v, self.bools_as_text))
     }
 
     fn serialize_i8(self, v: i8) -> Result<()> {
@@ -63,10 +302,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_i64(self, v: i64) -> Result<()> {
-        self.output += "\"";
-        self.output += &v.to_string();
-        self.output += "\"";
-        Ok(())
+        self.write_scalar(&v.to_string())
     }
 
     fn serialize_u8(self, v: u8) -> Result<()> {
@@ -82,10 +318,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_u64(self, v: u64) -> Result<()> {
-        self.output += "\"";
-        self.output += &v.to_string();
-        self.output += "\"";
-        Ok(())
+        self.write_scalar(&v.to_string())
     }
 
     fn serialize_f32(self, v: f32) -> Result<()> {
@@ -93,10 +326,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_f64(self, v: f64) -> Result<()> {
-        self.output += "\"";
-        self.output += &v.to_string();
-        self.output += "\"";
-        Ok(())
+        self.write_scalar(&v.to_string())
     }
 
     fn serialize_char(self, v: char) -> Result<()> {
@@ -104,21 +334,14 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_str(self, v: &str) -> Result<()> {
-        #[cfg(feature = "escape")]
-        let v = v
-            .replace('\\', r"\\")
-            .replace('\n', r"\n")
-            .replace('\t', r"\t")
-            .replace('"', r#"\""#);
-        
-        self.output += "\"";
-        self.output += &v;
-        self.output += "\"";
-        Ok(())
+        self.write_scalar(v)
     }
 
-    fn serialize_bytes(self, _v: &[u8]) -> Result<()> {
-        Err(Error::UnsupportedType("byte array"))
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        match self.byte_encoding {
+            Some(encoding) => self.serialize_str(&encoding.encode(v)),
+            None => Err(Error::UnsupportedType("byte array")),
+        }
     }
 
     fn serialize_none(self) -> Result<()> {
@@ -133,11 +356,12 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_unit(self) -> Result<()> {
-        Err(Error::UnsupportedType("unit"))
+        self.serialize_str("")
     }
 
-    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
-        Err(Error::UnsupportedType("unit_struct"))
+    fn serialize_unit_struct(self, name: &'static str) -> Result<()> {
+        self.open_group(Some(name))?;
+        self.close_group()
     }
 
     fn serialize_unit_variant(
@@ -157,8 +381,14 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         where
             T: ?Sized + Serialize,
     {
-        if self.indent_level == 0 {
-            self.output += &format!("\"{}\"\t", name);
+        if name == DOCUMENT && self.writer.depth() == 0 && self.pending_key.is_none() {
+            let bare = std::mem::replace(&mut self.bare_top_level, true);
+            let result = value.serialize(&mut *self);
+            self.bare_top_level = bare;
+            return result;
+        }
+        if self.writer.depth() == 0 && self.pending_key.is_none() {
+            self.pending_key = Some(name.to_string());
         }
         value.serialize(self)
     }
@@ -177,19 +407,30 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
-        Err(Error::UnsupportedType("seq"))
+        match self.pending_key.take() {
+            Some(key) if self.repeated_keys => Ok(RepeatedKey { ser: self, key }),
+            _ => Err(Error::UnsupportedType("seq")),
+        }
     }
 
     fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
-        Err(Error::UnsupportedType("tuple"))
+        self.open_group(None)?;
+        Ok(NumberedKeys {
+            ser: self,
+            index: 0,
+        })
     }
 
     fn serialize_tuple_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct> {
-        Err(Error::UnsupportedType("tuple_struct"))
+        self.open_group(Some(name))?;
+        Ok(NumberedKeys {
+            ser: self,
+            index: 0,
+        })
     }
 
     fn serialize_tuple_variant(
@@ -203,25 +444,17 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        if self.output.ends_with('\t') {
-            self.output.pop();
-        }
-        self.output += "\n";
-        self.output += &"\t".repeat(self.indent_level);
-        self.output += "{\n";
-        self.indent_level += 1;
+        self.open_group(None)?;
         Ok(self)
     }
 
     fn serialize_struct(
         self,
         name: &'static str,
-        len: usize,
+        _len: usize,
     ) -> Result<Self::SerializeStruct> {
-        if self.indent_level == 0 {
-            self.output += &format!("\"{}\"", name);
-        }
-        self.serialize_map(Some(len))
+        self.open_group(Some(name))?;
+        Ok(self)
     }
 
     fn serialize_struct_variant(
@@ -234,13 +467,13 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         Err(Error::UnsupportedType("struct_variant"))
     }
 
-    fn collect_str<T: ?Sized>(self, value: &T) -> Result<()> where
-        T: std::fmt::Display {
+    fn collect_str<T>(self, value: &T) -> Result<()> where
+        T: ?Sized + std::fmt::Display {
         self.serialize_str(&value.to_string())
     }
 }
 
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+impl<W: io::Write> ser::SerializeMap for &mut Serializer<W> {
     type Ok = ();
     type Error = Error;
 
@@ -248,9 +481,8 @@ impl<'a> ser::SerializeMap for &'a mut Serializer {
         where
             T: ?Sized + Serialize,
     {
-        self.output += &"\t".repeat(self.indent_level);
-        key.serialize(&mut **self)?;
-        self.output += "\t";
+        let key = key.serialize(MapKeySerializer { bools_as_text: self.bools_as_text })?;
+        self.pending_key = Some(key);
         Ok(())
     }
 
@@ -258,20 +490,15 @@ impl<'a> ser::SerializeMap for &'a mut Serializer {
         where
             T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)?;
-        self.output += "\n";
-        Ok(())
+        value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<()> {
-        self.indent_level = self.indent_level.saturating_sub(1);
-        self.output += &"\t".repeat(self.indent_level);
-        self.output += "}";
-        Ok(())
+        self.close_group()
     }
 }
 
-impl<'a> ser::SerializeStruct for &'a mut Serializer
+impl<W: io::Write> ser::SerializeStruct for &mut Serializer<W>
 {
     type Ok = ();
     type Error = Error;
@@ -280,8 +507,8 @@ impl<'a> ser::SerializeStruct for &'a mut Serializer
         where
             T: ?Sized + Serialize,
     {
-        self.serialize_key(key)?;
-        self.serialize_value(value)
+        self.pending_key = Some(key.to_string());
+        value.serialize(&mut **self)
     }
 
     fn end(self) -> Result<()> {
@@ -289,6 +516,227 @@ impl<'a> ser::SerializeStruct for &'a mut Serializer
     }
 }
 
+/// Serializes map keys, which can only be types that make sense as a single string
+pub(crate) struct MapKeySerializer {
+    pub(crate) bools_as_text: bool,
+}
+
+macro_rules! forward_to_to_string {
+    ($($method:ident($type:ty))*) => {
+        $(
+            fn $method(self, v: $type) -> Result<String> {
+                Ok(v.to_string())
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for MapKeySerializer {
+    type Ok = String;
+
+    type Error = Error;
+
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    forward_to_to_string! {
+        serialize_i8(i8) serialize_i16(i16) serialize_i32(i32) serialize_i64(i64)
+        serialize_u8(u8) serialize_u16(u16) serialize_u32(u32) serialize_u64(u64)
+        serialize_char(char) serialize_str(&str)
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<String> {
+        Ok(bool_text(v, self.bools_as_text).to_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<String> {
+        Err(Error::UnsupportedKey("f32"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<String> {
+        Err(Error::UnsupportedKey("f64"))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String> {
+        Err(Error::UnsupportedKey("byte array"))
+    }
+
+    fn serialize_none(self) -> Result<String> {
+        Err(Error::UnsupportedKey("option"))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<String>
+        where
+            T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Err(Error::UnsupportedKey("unit"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String> {
+        Err(Error::UnsupportedKey("unit_struct"))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String>
+        where
+            T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String>
+        where
+            T: ?Sized + Serialize,
+    {
+        Err(Error::UnsupportedKey("newtype_variant"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(Error::UnsupportedKey("seq"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(Error::UnsupportedKey("tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(Error::UnsupportedKey("tuple_struct"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Error::UnsupportedKey("tuple_variant"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Error::UnsupportedKey("map"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct> {
+        Err(Error::UnsupportedKey("struct"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Error::UnsupportedKey("struct_variant"))
+    }
+
+    fn collect_str<T>(self, value: &T) -> Result<String> where
+        T: ?Sized + std::fmt::Display {
+        Ok(value.to_string())
+    }
+}
+
+/// Sequences are written as the same key over and over, once for each element, so they
+/// can only go under a key and an empty one isn't written at all
+pub struct RepeatedKey<'a, W: io::Write = Vec<u8>> {
+    ser: &'a mut Serializer<W>,
+    key: String,
+}
+
+impl<W: io::Write> ser::SerializeSeq for RepeatedKey<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+        where
+            T: ?Sized + Serialize,
+    {
+        self.ser.pending_key = Some(self.key.clone());
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Tuples are written as groups with the keys `"0"`, `"1"`, and so on
+pub struct NumberedKeys<'a, W: io::Write = Vec<u8>> {
+    ser: &'a mut Serializer<W>,
+    index: usize,
+}
+
+impl<W: io::Write> ser::SerializeTuple for NumberedKeys<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+        where
+            T: ?Sized + Serialize,
+    {
+        self.ser.pending_key = Some(self.index.to_string());
+        value.serialize(&mut *self.ser)?;
+        self.index += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<()> {
+        self.ser.close_group()
+    }
+}
+
+impl<W: io::Write> ser::SerializeTupleStruct for NumberedKeys<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+        where
+            T: ?Sized + Serialize,
+    {
+        ser::SerializeTuple::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<()> {
+        ser::SerializeTuple::end(self)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -327,4 +775,135 @@ mod tests {
         );
         assert_eq!(to_string(&test).unwrap(), expected);
     }
+
+    #[test]
+    fn test_unit_and_tuples() {
+        #[derive(Serialize)]
+        struct Marker;
+
+        #[derive(Serialize)]
+        struct Pair(u8, String);
+
+        #[derive(Serialize)]
+        struct Test {
+            unit: (),
+            marker: Marker,
+            pair: Pair,
+        }
+
+        let test = Test {
+            unit: (),
+            marker: Marker,
+            pair: Pair(7, "seven".to_string()),
+        };
+        let expected = concat!(
+            "\"Test\"\n",
+            "{\n",
+            "\t\"unit\"\t\"\"\n",
+            "\t\"marker\"\n",
+            "\t{\n",
+            "\t}\n",
+            "\t\"pair\"\n",
+            "\t{\n",
+            "\t\t\"0\"\t\"7\"\n",
+            "\t\t\"1\"\t\"seven\"\n",
+            "\t}\n",
+            "}"
+        );
+        assert_eq!(to_string(&test).unwrap(), expected);
+        assert_eq!(to_string(&Marker).unwrap(), "\"Marker\"\n{\n}");
+    }
+
+    #[test]
+    fn test_seqs() {
+        #[derive(Serialize)]
+        struct Side {
+            id: u32,
+        }
+
+        #[derive(Serialize)]
+        struct Solid {
+            id: u32,
+            side: Vec<Side>,
+            hidden: Vec<String>,
+        }
+
+        let solid = Solid {
+            id: 1,
+            side: vec![Side { id: 2 }, Side { id: 3 }],
+            hidden: vec![],
+        };
+        let expected = concat!(
+            "\"id\"\t\"1\"\n",
+            "\"side\"\n{\n\t\"id\"\t\"2\"\n}\n",
+            "\"side\"\n{\n\t\"id\"\t\"3\"\n}"
+        );
+        let mut serializer = Serializer::new().bare_top_level(true).repeated_keys(true);
+        solid.serialize(&mut serializer).unwrap();
+        assert_eq!(serializer.into_string(), expected);
+        let mut serializer = Serializer::new().repeated_keys(true);
+        solid.serialize(&mut serializer).unwrap();
+        assert!(serializer.into_string().starts_with("\"Solid\"\n{\n\t\"id\"\t\"1\"\n\t\"side\"\n\t{"));
+        // the default can't read them back, so it doesn't write them
+        assert_eq!(to_string(&solid), Err(Error::UnsupportedType("seq")));
+        let mut serializer = Serializer::new().repeated_keys(true);
+        assert_eq!(vec![1, 2].serialize(&mut serializer), Err(Error::UnsupportedType("seq")));
+    }
+
+    #[test]
+    fn test_map_keys() {
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+        enum Branch { Public, Beta }
+
+        let apps = vec![(440u32, "Team Fortress 2"), (620, "Portal 2")].into_iter().collect::<BTreeMap<_, _>>();
+        assert_eq!(to_string(&apps).unwrap(), "\n{\n\t\"440\"\t\"Team Fortress 2\"\n\t\"620\"\t\"Portal 2\"\n}");
+
+        let branches = vec![(Branch::Public, true), (Branch::Beta, false)].into_iter().collect::<BTreeMap<_, _>>();
+        assert_eq!(to_string(&branches).unwrap(), "\n{\n\t\"Public\"\t\"1\"\n\t\"Beta\"\t\"0\"\n}");
+
+        let mut complex = BTreeMap::new();
+        complex.insert((1, 2), "nope");
+        assert_eq!(to_string(&complex), Err(Error::UnsupportedKey("tuple")));
+    }
+
+    #[test]
+    fn test_to_writer() {
+        #[derive(Serialize)]
+        struct Wrapper(String);
+
+        let mut output = Vec::new();
+        to_writer(&mut output, &Wrapper("wrapped".to_string())).unwrap();
+        assert_eq!(output, b"\"Wrapper\"\t\"wrapped\"");
+
+        struct Broken;
+        impl io::Write for Broken {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::Other, "disk full"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        assert_eq!(to_writer(Broken, &()), Err(Error::Io("disk full".to_string())));
+    }
+
+    #[test]
+    fn test_to_bytes_with_encoding() {
+        #[derive(Serialize)]
+        struct Mod {
+            title: String,
+        }
+
+        let title = |title: &str| Mod { title: title.to_string() };
+        assert_eq!(to_bytes_with_encoding(&title("Caf\u{e9}"), Encoding::Latin1).unwrap(), b"\"Mod\"\n{\n\t\"title\"\t\"Caf\xe9\"\n}");
+        let utf16 = to_bytes_with_encoding(&title("x"), Encoding::Utf16Be).unwrap();
+        assert_eq!(&utf16[..4], b"\xfe\xff\0\"");
+        // the offset is into the encoded bytes, after the name, the start of the group, and a one-byte "\u{e9}"
+        assert_eq!(
+            to_bytes_with_encoding(&title("\u{e9}\u{3a9}mega"), Encoding::Windows1252),
+            Err(Error::Unmappable { encoding: Encoding::Windows1252, offset: 19 })
+        );
+    }
 }
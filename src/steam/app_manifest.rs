@@ -0,0 +1,72 @@
+//! `appmanifest_<appid>.acf`, which Steam keeps for each installed app
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// What's installed of an app, from `steamapps/appmanifest_<appid>.acf`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "AppState", rename_all = "PascalCase")]
+pub struct AppManifest {
+    /// The app's ID
+    #[serde(rename = "appid")]
+    pub app_id: u32,
+    /// Which Steam universe it's from, which is 1 for the public one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub universe: Option<u32>,
+    /// The Steam executable that installed it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub launcher_path: Option<String>,
+    /// The app's name
+    #[serde(rename = "name")]
+    pub name: String,
+    /// Flags for what state the install is in, like 4 for fully installed
+    #[serde(default)]
+    pub state_flags: u32,
+    /// The app's directory under `steamapps/common`
+    #[serde(rename = "installdir")]
+    pub install_dir: String,
+    /// When it was last updated, as a Unix timestamp
+    #[serde(default)]
+    pub last_updated: u64,
+    /// How much space it takes up, in bytes
+    #[serde(default)]
+    pub size_on_disk: u64,
+    /// The build that's installed
+    #[serde(rename = "buildid", default)]
+    pub build_id: u64,
+    /// The SteamID64 of the account that installed it
+    #[serde(default)]
+    pub last_owner: u64,
+    /// The depots that are installed, by depot ID
+    #[serde(default)]
+    pub installed_depots: BTreeMap<u32, InstalledDepot>,
+    /// Depots that come from another app instead, by depot ID, with the ID of that app
+    #[serde(default)]
+    pub shared_depots: BTreeMap<u32, u32>,
+    /// Settings picked for the app, like its `language`
+    #[serde(default)]
+    pub user_config: BTreeMap<String, String>,
+    /// The settings the installed files actually match
+    #[serde(default)]
+    pub mounted_config: BTreeMap<String, String>,
+}
+
+impl AppManifest {
+    /// Returns whether the app is fully installed, going by its state flags
+    pub fn is_fully_installed(&self) -> bool {
+        self.state_flags & 4 != 0
+    }
+}
+
+/// One depot of an installed app
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledDepot {
+    /// The ID of the depot's manifest that's installed
+    pub manifest: u64,
+    /// How big it is, in bytes
+    pub size: u64,
+    /// The DLC it's part of, if it's a DLC depot
+    #[serde(rename = "dlcappid", skip_serializing_if = "Option::is_none")]
+    pub dlc_app_id: Option<u32>,
+}
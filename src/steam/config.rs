@@ -0,0 +1,108 @@
+//! `config.vdf` and `localconfig.vdf`, which keep Steam's settings for the computer and for each user
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Steam's settings for the whole computer, from `config/config.vdf`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "InstallConfigStore", rename_all = "PascalCase")]
+pub struct Config {
+    /// Where the settings are kept, under `Software/Valve/Steam` like in the Windows registry
+    pub software: Software<SteamConfig>,
+}
+
+impl Config {
+    /// Returns the settings under `Software/Valve/Steam`
+    pub fn steam(&self) -> &SteamConfig {
+        &self.software.valve.steam
+    }
+}
+
+/// One user's settings, from `userdata/<account id>/config/localconfig.vdf`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "UserLocalConfigStore", rename_all = "PascalCase")]
+pub struct LocalConfig {
+    /// Where the settings are kept, under `Software/Valve/Steam` like in the Windows registry
+    pub software: Software<SteamLocalConfig>,
+}
+
+impl LocalConfig {
+    /// Returns the settings under `Software/Valve/Steam`
+    pub fn steam(&self) -> &SteamLocalConfig {
+        &self.software.valve.steam
+    }
+}
+
+/// The `Software` group of [`Config`](struct.Config.html) or [`LocalConfig`](struct.LocalConfig.html)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Software<T> {
+    /// The `Valve` group inside it
+    pub valve: Valve<T>,
+}
+
+/// The `Software/Valve` group of [`Config`](struct.Config.html) or [`LocalConfig`](struct.LocalConfig.html)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Valve<T> {
+    /// The `Steam` group inside it, where the settings are
+    pub steam: T,
+}
+
+/// The settings in [`Config`](struct.Config.html)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct SteamConfig {
+    /// Accounts that have logged in, by account name
+    #[serde(default)]
+    pub accounts: BTreeMap<String, Account>,
+    /// Which compatibility tool, like a Proton version, runs each app, by app ID,
+    /// where app 0 is the default for every app
+    #[serde(default)]
+    pub compat_tool_mapping: BTreeMap<u32, CompatTool>,
+}
+
+/// An account in [`SteamConfig`](struct.SteamConfig.html)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Account {
+    /// The account's SteamID64
+    #[serde(rename = "SteamID")]
+    pub steam_id: u64,
+}
+
+/// The compatibility tool picked for an app
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompatTool {
+    /// The tool's internal name, like `proton_experimental`
+    pub name: String,
+    /// Options for the tool, separated by commas
+    #[serde(default)]
+    pub config: String,
+    /// Which setting wins when more than one applies, where higher numbers win
+    #[serde(default)]
+    pub priority: u32,
+}
+
+/// The settings in [`LocalConfig`](struct.LocalConfig.html)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SteamLocalConfig {
+    /// Per-app settings and stats, by app ID
+    #[serde(default)]
+    pub apps: BTreeMap<u32, LocalApp>,
+}
+
+/// One app's settings and stats for a user
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LocalApp {
+    /// When the user last played it, as a Unix timestamp
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_played: Option<u64>,
+    /// How long the user has played it, in minutes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playtime: Option<u64>,
+    /// The launch options the user set for it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub launch_options: Option<String>,
+}
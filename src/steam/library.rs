@@ -0,0 +1,193 @@
+//! Finding library folders and installed apps under a Steam directory
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+
+use crate::encoding::decode;
+use crate::error::{Error, Result};
+
+use super::{AppManifest, LibraryFolder, LibraryFolders};
+
+/// The library folders of a Steam install, and the apps installed in them
+///
+/// Everything is read straight from the filesystem, starting from `steamapps/libraryfolders.vdf`
+/// under the directory Steam is installed in, which is always a library folder itself.
+///
+/// ```no_run
+/// use vdf_serde::steam::Library;
+///
+/// let library = Library::open("/home/chell/.local/share/Steam")?;
+/// for app in library.apps()? {
+///     println!("{} is in {} and takes up {} bytes", app.manifest.name, app.install_path.display(), app.manifest.size_on_disk);
+/// }
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Library {
+    root: PathBuf,
+    folders: Vec<Folder>,
+}
+
+/// A library folder, where Steam installs apps
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Folder {
+    /// Where it is
+    pub path: PathBuf,
+    /// What `libraryfolders.vdf` says about it, if it says anything, which the old format
+    /// doesn't for the folder Steam is installed in
+    pub info: Option<LibraryFolder>,
+}
+
+/// An app with a manifest in a library folder
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstalledApp {
+    /// What the manifest says, including the app's size on disk
+    pub manifest: AppManifest,
+    /// Where the manifest is, under `steamapps`
+    pub manifest_path: PathBuf,
+    /// Where the app's files are, under `steamapps/common`
+    pub install_path: PathBuf,
+}
+
+impl Library {
+    /// Reads the library folders of the Steam install in `root`
+    ///
+    /// Relative paths in `libraryfolders.vdf` are taken relative to `root`, and folders listed
+    /// more than once are only kept once. A missing `libraryfolders.vdf` means `root` is the only folder.
+    ///
+    /// # Errors
+    ///
+    /// If `root` doesn't have a `steamapps` directory in it, or `libraryfolders.vdf` can't be read,
+    /// an [`Error::File`](../enum.Error.html#variant.File) will be returned.
+    pub fn open(root: impl AsRef<Path>) -> Result<Library> {
+        let root = root.as_ref().to_path_buf();
+        let steamapps = root.join("steamapps");
+        if !steamapps.is_dir() {
+            return Err(file_error(&root, Error::Message("there's no steamapps directory in it".to_string())));
+        }
+        let list = steamapps.join("libraryfolders.vdf");
+        let listed = if list.is_file() { read::<LibraryFolders>(&list)?.0 } else { Default::default() };
+
+        let mut folders = vec![Folder { path: root.clone(), info: None }];
+        for (_, info) in listed {
+            let path = root.join(&info.path);
+            match folders.iter_mut().find(|folder| same_path(&folder.path, &path)) {
+                Some(folder) => folder.info = folder.info.take().or(Some(info)),
+                None => folders.push(Folder { path, info: Some(info) }),
+            }
+        }
+        Ok(Library { root, folders })
+    }
+
+    /// Returns the directory Steam is installed in
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the library folders, starting with the one Steam is installed in
+    pub fn folders(&self) -> &[Folder] {
+        &self.folders
+    }
+
+    /// Reads the manifest of every installed app, in the order of their folders and then their IDs
+    ///
+    /// Folders that aren't there, like ones on a drive that's been unplugged, are skipped.
+    ///
+    /// # Errors
+    ///
+    /// If a manifest can't be read, an [`Error::File`](../enum.Error.html#variant.File) will be returned.
+    pub fn apps(&self) -> Result<Vec<InstalledApp>> {
+        let mut apps = Vec::new();
+        for folder in self.folders.iter().filter(|folder| folder.is_available()) {
+            apps.extend(folder.apps()?);
+        }
+        Ok(apps)
+    }
+
+    /// Reads the manifest of one app, from whichever folder it's installed in
+    ///
+    /// # Errors
+    ///
+    /// If the manifest can't be read, an [`Error::File`](../enum.Error.html#variant.File) will be returned.
+    pub fn app(&self, app_id: u32) -> Result<Option<InstalledApp>> {
+        for folder in &self.folders {
+            let manifest_path = folder.steamapps().join(format!("appmanifest_{}.acf", app_id));
+            if manifest_path.is_file() {
+                return folder.app(manifest_path).map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Folder {
+    /// Returns the folder's `steamapps` directory, where the app manifests are
+    pub fn steamapps(&self) -> PathBuf {
+        self.path.join("steamapps")
+    }
+
+    /// Returns whether the folder is there to be read
+    pub fn is_available(&self) -> bool {
+        self.steamapps().is_dir()
+    }
+
+    /// Reads the manifest of every app installed in this folder, in order of their IDs
+    ///
+    /// # Errors
+    ///
+    /// If the folder or a manifest in it can't be read, an [`Error::File`](../enum.Error.html#variant.File) will be returned.
+    pub fn apps(&self) -> Result<Vec<InstalledApp>> {
+        let steamapps = self.steamapps();
+        let entries = fs::read_dir(&steamapps).map_err(|err| file_error(&steamapps, Error::Io(err.to_string())))?;
+        let mut manifests = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|err| file_error(&steamapps, Error::Io(err.to_string())))?.path();
+            let app_id = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("appmanifest_"))
+                .and_then(|name| name.strip_suffix(".acf"))
+                .and_then(|id| id.parse::<u32>().ok());
+            if let Some(app_id) = app_id {
+                manifests.push((app_id, path));
+            }
+        }
+        manifests.sort();
+        manifests.into_iter().map(|(_, path)| self.app(path)).collect()
+    }
+
+    fn app(&self, manifest_path: PathBuf) -> Result<InstalledApp> {
+        let manifest: AppManifest = read(&manifest_path)?;
+        let install_path = self.steamapps().join("common").join(&manifest.install_dir);
+        Ok(InstalledApp { manifest, manifest_path, install_path })
+    }
+}
+
+fn file_error(path: &Path, error: Error) -> Error {
+    Error::File {
+        path: path.display().to_string(),
+        error: Box::new(error),
+    }
+}
+
+/// Reads one of Steam's files, with its path in any error
+fn read<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let bytes = fs::read(path).map_err(|err| file_error(path, Error::Io(err.to_string())))?;
+    decode(&bytes).and_then(|text| super::from_str(&text)).map_err(|err| file_error(path, err))
+}
+
+/// Compares paths the way Steam would, which ignores case and trailing separators on Windows
+///
+/// Paths that exist are compared after resolving symlinks, like `~/.steam/steam` pointing at the
+/// real install.
+fn same_path(a: &Path, b: &Path) -> bool {
+    let a = fs::canonicalize(a).unwrap_or_else(|_| a.to_path_buf());
+    let b = fs::canonicalize(b).unwrap_or_else(|_| b.to_path_buf());
+    if cfg!(windows) {
+        a.to_string_lossy().trim_end_matches(['\\', '/']).eq_ignore_ascii_case(b.to_string_lossy().trim_end_matches(['\\', '/']))
+    } else {
+        a.components().eq(b.components())
+    }
+}
@@ -0,0 +1,146 @@
+//! `libraryfolders.vdf`, the list of places Steam installs games to
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{Deserialize, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+/// Steam's library folders, from `steamapps/libraryfolders.vdf`, by their number in the file
+///
+/// Both formats of the file can be read. The old one, from before 2021, is just a path for each folder,
+/// and leaves out the folder Steam itself is installed in, which is folder 0 in the new format.
+/// Other entries in the old format, like `ContentStatsID`, are skipped. This is always written in the new format.
+///
+/// ```
+/// use vdf_serde::steam::{self, LibraryFolders};
+///
+/// let old: LibraryFolders = steam::from_str(r#""LibraryFolders" { "ContentStatsID" "-1" "1" "D:\\SteamLibrary" }"#)?;
+/// assert_eq!(old.0[&1].path, r"D:\SteamLibrary");
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LibraryFolders(pub BTreeMap<u32, LibraryFolder>);
+
+/// One library folder
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LibraryFolder {
+    /// Where it is, in the format of the computer the file is from
+    pub path: String,
+    /// The name the user gave it, which is usually empty
+    pub label: String,
+    /// An ID for what's in it, which Steam uses to tell when the folder has changed
+    pub content_id: Option<u64>,
+    /// The size of the drive it's on, in bytes, or 0 if Steam hasn't checked
+    pub total_size: Option<u64>,
+    /// The apps installed in it, by app ID, with how much space they take up in bytes
+    pub apps: BTreeMap<u32, u64>,
+}
+
+impl LibraryFolder {
+    /// Returns whether an app is installed in this folder
+    pub fn contains(&self, app_id: u32) -> bool {
+        self.apps.contains_key(&app_id)
+    }
+}
+
+impl<'de> Deserialize<'de> for LibraryFolders {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FoldersVisitor;
+
+        impl<'de> Visitor<'de> for FoldersVisitor {
+            type Value = LibraryFolders;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a group of library folders")
+            }
+
+            fn visit_newtype_struct<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+                deserializer.deserialize_map(self)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut folders = BTreeMap::new();
+                while let Some(key) = access.next_key::<String>()? {
+                    match key.parse() {
+                        Ok(number) => {
+                            folders.insert(number, access.next_value()?);
+                        }
+                        Err(_) => {
+                            access.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(LibraryFolders(folders))
+            }
+        }
+
+        deserializer.deserialize_newtype_struct("libraryfolders", FoldersVisitor)
+    }
+}
+
+impl Serialize for LibraryFolders {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct("libraryfolders", &self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for LibraryFolder {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FolderVisitor;
+
+        impl<'de> Visitor<'de> for FolderVisitor {
+            type Value = LibraryFolder;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a path or a group describing a library folder")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, path: &str) -> Result<Self::Value, E> {
+                Ok(LibraryFolder {
+                    path: path.to_string(),
+                    ..LibraryFolder::default()
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut folder = LibraryFolder::default();
+                let mut path = None;
+                while let Some(key) = access.next_key::<String>()? {
+                    match key.to_ascii_lowercase().as_str() {
+                        "path" => path = Some(access.next_value()?),
+                        "label" => folder.label = access.next_value()?,
+                        "contentid" => folder.content_id = Some(access.next_value()?),
+                        "totalsize" => folder.total_size = Some(access.next_value()?),
+                        "apps" => folder.apps = access.next_value()?,
+                        _ => {
+                            access.next_value::<IgnoredAny>()?;
+                        }
+                    }
+                }
+                folder.path = path.ok_or_else(|| serde::de::Error::missing_field("path"))?;
+                Ok(folder)
+            }
+        }
+
+        deserializer.deserialize_any(FolderVisitor)
+    }
+}
+
+impl Serialize for LibraryFolder {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut group = serializer.serialize_struct("LibraryFolder", 5)?;
+        group.serialize_field("path", &self.path)?;
+        group.serialize_field("label", &self.label)?;
+        match self.content_id {
+            Some(content_id) => group.serialize_field("contentid", &content_id)?,
+            None => group.skip_field("contentid")?,
+        }
+        match self.total_size {
+            Some(total_size) => group.serialize_field("totalsize", &total_size)?,
+            None => group.skip_field("totalsize")?,
+        }
+        group.serialize_field("apps", &self.apps)?;
+        group.end()
+    }
+}
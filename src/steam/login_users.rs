@@ -0,0 +1,46 @@
+//! `loginusers.vdf`, the accounts that have logged in to Steam on this computer
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// The accounts that have logged in, from `config/loginusers.vdf`, by SteamID64
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "users")]
+pub struct LoginUsers(pub BTreeMap<u64, LoginUser>);
+
+impl LoginUsers {
+    /// Returns the account that logged in most recently, with its SteamID64
+    pub fn most_recent(&self) -> Option<(u64, &LoginUser)> {
+        self.0.iter().find(|(_, user)| user.most_recent).map(|(&id, user)| (id, user))
+    }
+}
+
+/// One account that has logged in
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LoginUser {
+    /// The name the account logs in with
+    pub account_name: String,
+    /// The name other people see
+    #[serde(default)]
+    pub persona_name: String,
+    /// Whether Steam remembers the password
+    #[serde(default)]
+    pub remember_password: bool,
+    /// Whether Steam starts in offline mode
+    #[serde(default)]
+    pub wants_offline_mode: bool,
+    /// Whether the offline mode warning is turned off
+    #[serde(default)]
+    pub skip_offline_mode_warning: bool,
+    /// Whether Steam logs in as this account without asking
+    #[serde(default)]
+    pub allow_auto_login: bool,
+    /// Whether this is the account that logged in last, which older files write as `mostrecent`
+    #[serde(default)]
+    pub most_recent: bool,
+    /// When the account last logged in, as a Unix timestamp
+    #[serde(default)]
+    pub timestamp: u64,
+}
@@ -0,0 +1,56 @@
+//! Models for the VDF files the Steam client keeps its state in
+//!
+//! Each file has a type here that reads and writes it with [`from_str`](fn.from_str.html) and
+//! [`to_string`](../fn.to_string.html), including its root name:
+//!
+//! | File | Type |
+//! |------|------|
+//! | `steamapps/libraryfolders.vdf`, old and new formats | [`LibraryFolders`](struct.LibraryFolders.html) |
+//! | `steamapps/appmanifest_<appid>.acf` | [`AppManifest`](struct.AppManifest.html) |
+//! | `config/loginusers.vdf` | [`LoginUsers`](struct.LoginUsers.html) |
+//! | `config/config.vdf` | [`Config`](struct.Config.html) |
+//! | `userdata/<account id>/config/localconfig.vdf` | [`LocalConfig`](struct.LocalConfig.html) |
+//!
+//! [`Library`](struct.Library.html) puts `libraryfolders.vdf` and the app manifests together, to find
+//! every installed app from the directory Steam is installed in.
+//!
+//! Only the parts of each file that are useful and stable are modelled, and everything else is skipped
+//! when reading, so writing one of these back out loses whatever wasn't modelled. To edit a file without
+//! losing anything, use a [`Value`](../enum.Value.html) instead.
+//!
+//! ```
+//! use vdf_serde::steam::{self, AppManifest};
+//!
+//! let app: AppManifest = steam::from_str(r#""AppState" { "appid" "620" "name" "Portal 2" "installdir" "Portal 2" }"#)?;
+//! assert_eq!((app.app_id, app.install_dir.as_str()), (620, "Portal 2"));
+//! # Ok::<(), vdf_serde::Error>(())
+//! ```
+
+use serde::Deserialize;
+
+use crate::de::Deserializer;
+use crate::error::Result;
+
+mod app_manifest;
+mod config;
+mod library;
+mod library_folders;
+mod login_users;
+
+pub use app_manifest::{AppManifest, InstalledDepot};
+pub use config::{Account, CompatTool, Config, LocalApp, LocalConfig, Software, SteamConfig, SteamLocalConfig, Valve};
+pub use library::{Folder, InstalledApp, Library};
+pub use library_folders::{LibraryFolder, LibraryFolders};
+pub use login_users::{LoginUser, LoginUsers};
+
+/// Deserialize one of Steam's files, matching keys and root names regardless of case like Steam does
+///
+/// # Errors
+///
+/// The same as [`vdf_serde::from_str`](../fn.from_str.html).
+pub fn from_str<'a, T>(s: &'a str) -> Result<T> where T: Deserialize<'a> {
+    let mut deserializer = Deserializer::from_str(s).case_insensitive(true);
+    let t = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(t)
+}
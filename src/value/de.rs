@@ -0,0 +1,390 @@
+//! Deserialize a `Value` into a Rust data structure
+
+use std::collections::{HashSet, VecDeque};
+use std::str::FromStr;
+
+use serde::de::{self, DeserializeOwned, IntoDeserializer, MapAccess, SeqAccess, Visitor};
+
+use crate::error::{Error, Result};
+use crate::map::Map;
+
+use super::Value;
+
+/// Convert a [`Value`](enum.Value.html) into a `T`
+///
+/// This is the other half of [`to_value`](fn.to_value.html), so the top level isn't special here either,
+/// which makes it handy for turning one part of a parsed document into a struct. A key that's asked
+/// for as a sequence gets every value it has in its group, like with
+/// [`DuplicateKeys::Collect`](enum.DuplicateKeys.html#variant.Collect), and anything that takes
+/// whatever it's given sees a group where a key repeats as a sequence of one-entry groups, the same
+/// as when reading text.
+///
+/// ```
+/// use serde::Deserialize;
+/// use vdf_serde::Value;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct UserConfig {
+///     language: String,
+/// }
+///
+/// let mut document: Value = r#""AppState" { "appid" "620" "UserConfig" { "language" "english" } }"#.parse()?;
+/// let app = document.get_mut("AppState").unwrap();
+/// *app.get_mut("UserConfig").and_then(|config| config.get_mut("language")).unwrap() = "french".into();
+/// let config: UserConfig = vdf_serde::from_value(app.get("UserConfig").unwrap().clone())?;
+/// assert_eq!(config, UserConfig { language: "french".to_string() });
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// If the value doesn't have the shape `T` expects, or a string in it can't be parsed
+/// into what `T` wants there, an error will be returned.
+pub fn from_value<T>(value: Value) -> Result<T>
+    where
+        T: DeserializeOwned,
+{
+    T::deserialize(value)
+}
+
+impl Value {
+    fn into_string(self) -> Result<String> {
+        match self {
+            Value::String(text) => Ok(text),
+            Value::Group(_) => Err(Error::Expected("string", "group".to_string())),
+        }
+    }
+
+    fn into_group(self) -> Result<Map<String, Value>> {
+        match self {
+            Value::String(text) => Err(Error::Expected("group", format!("{:?}", text))),
+            Value::Group(group) => Ok(group),
+        }
+    }
+
+    fn parse_string<T: FromStr>(self) -> Result<T> where T::Err: std::fmt::Display {
+        self.into_string()?.parse().map_err(|err: T::Err| Error::StringParse(err.to_string()))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                visitor.$visit(self.parse_string()?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    /// Strings are visited as strings, and groups as maps, except that a group where a key repeats
+    /// is visited as a sequence of one-entry maps so that nothing gets lost
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::String(text) => visitor.visit_string(text),
+            Value::Group(group) => {
+                let mut keys = HashSet::new();
+                if group.keys().all(|key| keys.insert(key)) {
+                    visitor.visit_map(Entries::new(group))
+                } else {
+                    let entries = group.into_iter().map(|(key, value)| {
+                        let mut entry = Map::with_capacity(1);
+                        entry.append(key, value);
+                        Value::Group(entry)
+                    });
+                    visitor.visit_seq(Values(entries.collect::<Vec<_>>().into_iter()))
+                }
+            }
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.into_string()?.as_str() {
+            "0" => visitor.visit_bool(false),
+            "1" => visitor.visit_bool(true),
+            got => Err(Error::Expected("bool (\"0\" or \"1\")", format!("{:?}", got))),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8
+        deserialize_i16 => visit_i16
+        deserialize_i32 => visit_i32
+        deserialize_i64 => visit_i64
+        deserialize_u8 => visit_u8
+        deserialize_u16 => visit_u16
+        deserialize_u32 => visit_u32
+        deserialize_u64 => visit_u64
+        deserialize_f32 => visit_f32
+        deserialize_f64 => visit_f64
+        deserialize_char => visit_char
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.into_string()?)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedType("byte array"))
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedType("byte array"))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Value::String(text) if text.is_empty() => visitor.visit_unit(),
+            Value::Group(group) if group.is_empty() => visitor.visit_unit(),
+            got => Err(Error::Expected("\"\" or {}", format!("{:?}", got))),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedType("seq"))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value> {
+        let values = match self {
+            Value::Group(group) => group.into_iter().enumerate().map(|(i, (key, value))| {
+                if key == i.to_string() {
+                    Ok(value)
+                } else {
+                    Err(Error::Expected("tuple keys \"0\", \"1\", and so on, in order", format!("{:?}", key)))
+                }
+            }).collect::<Result<Vec<_>>>()?,
+            Value::String(text) => text.split_whitespace().map(Value::from).collect(),
+        };
+        if values.len() != len {
+            return Err(Error::StringParse(format!("expected {} values, got {}", len, values.len())));
+        }
+        visitor.visit_seq(Values(values.into_iter()))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(Entries::new(self.into_group()?))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        visitor.visit_enum(self.into_string()?.into_deserializer())
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_string(visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        ignored_any
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// The entries of a group, handed out one at a time
+struct Entries {
+    entries: VecDeque<(String, Value)>,
+    current: Option<(String, Value)>,
+}
+
+impl Entries {
+    fn new(group: Map<String, Value>) -> Self {
+        Entries {
+            entries: group.into_iter().collect(),
+            current: None,
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for Entries {
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.entries.pop_front() {
+            Some((key, value)) => {
+                self.current = Some((key.clone(), value));
+                seed.deserialize(Key(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (key, value) = self.current.take().expect("value requested before key");
+        seed.deserialize(EntryValue { key, value, rest: &mut self.entries })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// The value of an entry, which takes the values of the entries after it with the same key if it's read as a sequence
+struct EntryValue<'a> {
+    key: String,
+    value: Value,
+    rest: &'a mut VecDeque<(String, Value)>,
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident($($arg:ident: $type:ty),*))*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($arg: $type,)* visitor: V) -> Result<V::Value> {
+                de::Deserializer::$method(self.value, $($arg,)* visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for EntryValue<'_> {
+    type Error = Error;
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let key = self.key;
+        let (repeats, rest): (VecDeque<_>, VecDeque<_>) = self.rest.drain(..).partition(|(other, _)| *other == key);
+        *self.rest = rest;
+        let values = std::iter::once(self.value).chain(repeats.into_iter().map(|(_, value)| value));
+        visitor.visit_seq(Values(values.collect::<Vec<_>>().into_iter()))
+    }
+
+    forward_to_value! {
+        deserialize_any() deserialize_bool()
+        deserialize_i8() deserialize_i16() deserialize_i32() deserialize_i64()
+        deserialize_u8() deserialize_u16() deserialize_u32() deserialize_u64()
+        deserialize_f32() deserialize_f64() deserialize_char()
+        deserialize_str() deserialize_string() deserialize_bytes() deserialize_byte_buf()
+        deserialize_option() deserialize_unit() deserialize_unit_struct(name: &'static str)
+        deserialize_newtype_struct(name: &'static str) deserialize_tuple(len: usize)
+        deserialize_tuple_struct(name: &'static str, len: usize) deserialize_map()
+        deserialize_struct(name: &'static str, fields: &'static [&'static str])
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+        deserialize_identifier() deserialize_ignored_any()
+    }
+}
+
+/// The values of a tuple, handed out one at a time
+struct Values(std::vec::IntoIter<Value>);
+
+impl<'de> SeqAccess<'de> for Values {
+    type Error = Error;
+
+    fn next_element_seed<T: de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        self.0.next().map(|value| seed.deserialize(value)).transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// Deserializes a group's key, which can be read as anything that makes sense as a single string
+struct Key(String);
+
+macro_rules! forward_key_to_value {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                de::Deserializer::$method(Value::String(self.0), visitor)
+            }
+        )*
+    };
+}
+
+macro_rules! unsupported_key {
+    ($($method:ident => $type:literal)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value> {
+                Err(Error::UnsupportedKey($type))
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Key {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.0)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.0)
+    }
+
+    forward_key_to_value! {
+        deserialize_bool
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_char deserialize_str deserialize_string deserialize_identifier
+    }
+
+    unsupported_key! {
+        deserialize_f32 => "f32"
+        deserialize_f64 => "f64"
+        deserialize_bytes => "byte array"
+        deserialize_byte_buf => "byte array"
+        deserialize_unit => "unit"
+        deserialize_seq => "seq"
+        deserialize_map => "map"
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedKey("unit_struct"))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedKey("tuple"))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _name: &'static str, _len: usize, _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedKey("tuple_struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value> {
+        Err(Error::UnsupportedKey("struct"))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_enum(Value::String(self.0), name, variants, visitor)
+    }
+}
@@ -0,0 +1,270 @@
+//! Find what changed between two documents
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::map::Map;
+use crate::writer::Writer;
+
+use super::query::push_key;
+use super::{write_entries, Value};
+
+/// One difference found by [`diff`](fn.diff.html)
+///
+/// Paths are [`Query`](struct.Query.html)-style, with an `[n]` after any key that appears more than once
+/// in its group on either side. Duplicate keys are matched up in order, so the first `"a"` in one group
+/// is compared with the first `"a"` in the other, and so on.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DiffEntry {
+    /// An entry only in the new value
+    Added {
+        /// Where it is
+        path: String,
+        /// What it is
+        value: Value,
+    },
+    /// An entry only in the old value
+    Removed {
+        /// Where it was
+        path: String,
+        /// What it was
+        value: Value,
+    },
+    /// An entry in both, with a different value, which is never two groups since those get compared entry by entry
+    Changed {
+        /// Where it is
+        path: String,
+        /// What it was
+        old: Value,
+        /// What it is now
+        new: Value,
+    },
+    /// An entry in both that isn't in the same order relative to the others any more
+    Moved {
+        /// Where it is
+        path: String,
+        /// Its position in its group in the old value, counting from 0
+        from: usize,
+        /// Its position in its group in the new value, counting from 0
+        to: usize,
+    },
+}
+
+impl DiffEntry {
+    /// Returns the path of the entry this is about
+    pub fn path(&self) -> &str {
+        match self {
+            DiffEntry::Added { path, .. }
+            | DiffEntry::Removed { path, .. }
+            | DiffEntry::Changed { path, .. }
+            | DiffEntry::Moved { path, .. } => path,
+        }
+    }
+}
+
+/// Everything that's different between two values, which displays in a style like a unified diff
+///
+/// ```
+/// use vdf_serde::vdf;
+///
+/// let old = vdf! { "Settings" { "volume" "0.5" "width" "1920" } };
+/// let new = vdf! { "Settings" { "width" "2560" "volume" "0.5" "vsync" "1" } };
+/// assert_eq!(vdf_serde::diff(&old, &new).to_string(), "\
+/// @@ Settings/width @@ moved from 1 to 0
+/// @@ Settings/width @@
+/// -\"1920\"
+/// +\"2560\"
+/// @@ Settings/vsync @@
+/// +\"1\"
+/// ");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Diff {
+    /// The differences, in the order of the old value and then the new one
+    pub entries: Vec<DiffEntry>,
+}
+
+impl Diff {
+    /// Returns whether the values were the same
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Compares two values and finds everything that's different between them
+pub fn diff(old: &Value, new: &Value) -> Diff {
+    let mut entries = Vec::new();
+    diff_values(old, new, "", &mut entries);
+    Diff { entries }
+}
+
+fn diff_values(old: &Value, new: &Value, path: &str, entries: &mut Vec<DiffEntry>) {
+    match (old, new) {
+        (Value::Group(old), Value::Group(new)) => diff_groups(old, new, path, entries),
+        (old, new) if old != new => entries.push(DiffEntry::Changed {
+            path: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Numbers each entry by how many times its key has already come up in the group
+fn occurrences(group: &Map<String, Value>) -> Vec<(&str, usize)> {
+    let mut seen = HashMap::new();
+    group
+        .keys()
+        .map(|key| {
+            let n = seen.entry(key.as_str()).or_insert(0);
+            *n += 1;
+            (key.as_str(), *n - 1)
+        })
+        .collect()
+}
+
+fn diff_groups(old: &Map<String, Value>, new: &Map<String, Value>, path: &str, entries: &mut Vec<DiffEntry>) {
+    let old_keys = occurrences(old);
+    let new_keys = occurrences(new);
+    let new_positions = new_keys.iter().enumerate().map(|(j, key)| (*key, j)).collect::<HashMap<_, _>>();
+    // an occurrence past the first means the key comes up more than once in that group
+    let repeated_keys = old_keys.iter().chain(&new_keys).filter(|(_, n)| *n > 0).map(|(key, _)| *key).collect::<HashSet<_>>();
+    let repeated = |key: &str| repeated_keys.contains(key);
+    let entry_path = |(key, n): (&str, usize)| {
+        let mut path = path.to_string();
+        push_key(&mut path, key, if repeated(key) { Some(n) } else { None });
+        path
+    };
+
+    let pairs = old_keys.iter().enumerate().filter_map(|(i, key)| new_positions.get(key).map(|&j| (i, j))).collect::<Vec<_>>();
+    let in_order = longest_increasing(&pairs.iter().map(|&(_, j)| j).collect::<Vec<_>>());
+
+    let old_values = old.values().collect::<Vec<_>>();
+    let new_values = new.values().collect::<Vec<_>>();
+    let mut pairs = pairs.into_iter().enumerate().peekable();
+    for (i, key) in old_keys.iter().enumerate() {
+        match pairs.peek() {
+            Some(&(k, (paired, j))) if paired == i => {
+                pairs.next();
+                let path = entry_path(*key);
+                if !in_order[k] {
+                    entries.push(DiffEntry::Moved { path: path.clone(), from: i, to: j });
+                }
+                diff_values(old_values[i], new_values[j], &path, entries);
+            }
+            _ => entries.push(DiffEntry::Removed {
+                path: entry_path(*key),
+                value: old_values[i].clone(),
+            }),
+        }
+    }
+    let old_positions = old_keys.iter().collect::<HashSet<_>>();
+    for (j, key) in new_keys.iter().enumerate() {
+        if !old_positions.contains(key) {
+            entries.push(DiffEntry::Added {
+                path: entry_path(*key),
+                value: new_values[j].clone(),
+            });
+        }
+    }
+}
+
+/// Marks which items are part of a longest increasing subsequence,
+/// so that everything else is the fewest entries that have to have moved
+fn longest_increasing(items: &[usize]) -> Vec<bool> {
+    // patience sorting: `tails[k]` is the item ending the lowest run of length `k + 1` found so far,
+    // and `previous` is the item before each one in its run
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous = vec![None; items.len()];
+    // the first item to end a run as long as any, so that of two ways to order things, the earlier entries stay put
+    let mut longest = None;
+    for (i, item) in items.iter().enumerate() {
+        let k = tails.partition_point(|&tail| items[tail] < *item);
+        previous[i] = k.checked_sub(1).map(|k| tails[k]);
+        if k == tails.len() {
+            tails.push(i);
+            longest = Some(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+    let mut in_run = vec![false; items.len()];
+    let mut next = longest;
+    while let Some(i) = next {
+        in_run[i] = true;
+        next = previous[i];
+    }
+    in_run
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            match entry {
+                DiffEntry::Moved { path, from, to } => writeln!(formatter, "@@ {} @@ moved from {} to {}", path, from, to)?,
+                DiffEntry::Added { path, value } => {
+                    writeln!(formatter, "@@ {} @@", path)?;
+                    write_lines(formatter, '+', value)?;
+                }
+                DiffEntry::Removed { path, value } => {
+                    writeln!(formatter, "@@ {} @@", path)?;
+                    write_lines(formatter, '-', value)?;
+                }
+                DiffEntry::Changed { path, old, new } => {
+                    writeln!(formatter, "@@ {} @@", path)?;
+                    write_lines(formatter, '-', old)?;
+                    write_lines(formatter, '+', new)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes a value as VDF, with `prefix` in front of every line
+fn write_lines(formatter: &mut fmt::Formatter, prefix: char, value: &Value) -> fmt::Result {
+    let mut writer = Writer::new(Vec::new());
+    let written = match value {
+        Value::String(text) => writer.write_value(text),
+        Value::Group(group) => writer
+            .begin_unnamed_group()
+            .and_then(|()| write_entries(&mut writer, group))
+            .and_then(|()| writer.end_group()),
+    };
+    written.map_err(|_| fmt::Error)?;
+    for line in String::from_utf8_lossy(&writer.into_inner()).lines() {
+        writeln!(formatter, "{}{}", prefix, line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vdf;
+
+    #[test]
+    fn test_diff() {
+        let old = vdf! { "a" "1" "dup" "x" "dup" "y" "group" { "k" "v" } "gone" "1" "c" "3" };
+        let new = vdf! { "c" "3" "a" "1" "dup" "x" "dup" "z" "dup" "w" "group" "flat" };
+        assert_eq!(diff(&old, &new).entries, [
+            DiffEntry::Changed { path: "dup[1]".to_string(), old: Value::from("y"), new: Value::from("z") },
+            DiffEntry::Changed { path: "group".to_string(), old: vdf! { "k" "v" }, new: Value::from("flat") },
+            DiffEntry::Removed { path: "gone".to_string(), value: Value::from("1") },
+            DiffEntry::Moved { path: "c".to_string(), from: 5, to: 0 },
+            DiffEntry::Added { path: "dup[2]".to_string(), value: Value::from("w") },
+        ]);
+        assert!(diff(&old, &old).is_empty());
+        assert_eq!(diff(&Value::from("a"), &Value::from("b")).entries[0].path(), "");
+
+        let rendered = diff(&vdf! { "g" { "k" "v" } }, &vdf! {}).to_string();
+        assert_eq!(rendered, "@@ g @@\n-{\n-\t\"k\"\t\"v\"\n-}\n");
+    }
+
+    #[test]
+    fn test_longest_increasing() {
+        assert!(longest_increasing(&[]).is_empty());
+        assert_eq!(longest_increasing(&[3, 0, 1, 4, 2]), [false, true, true, true, false]);
+        assert_eq!(longest_increasing(&[4, 3, 2, 1, 0]), [true, false, false, false, false]);
+    }
+}
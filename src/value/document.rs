@@ -0,0 +1,384 @@
+//! A VDF document that keeps its comments and layout when it's changed
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::map::Map;
+use crate::reader::{Event, Reader, Span};
+use crate::writer::Writer;
+
+use super::patch::{apply_op, conflict, path_segments};
+use super::{write_entries, Patch, PatchOp, Value};
+
+/// A VDF document as it was written, which a [`Patch`](struct.Patch.html) can change without
+/// losing its comments, its indentation, or the order of anything it doesn't touch
+///
+/// Replaced values go where the old ones were, removed entries take their line with them if they
+/// had it to themselves, and added entries go at the end of their group, indented like the entries
+/// before them. Groups inside new values are indented with whatever the document indents with.
+///
+/// ```
+/// use vdf_serde::{Document, Patch, PatchOp, Value};
+///
+/// let mut document: Document = "\"Settings\"\n{\n    // between 0 and 1\n    \"volume\"  \"0.5\"\n}\n".parse()?;
+/// let patch = Patch { ops: vec![
+///     PatchOp::Replace { path: "Settings/volume".to_string(), old: Some(Value::from("0.5")), value: Value::from("0.8") },
+///     PatchOp::Add { path: "Settings/vsync".to_string(), value: Value::from("1") },
+/// ] };
+/// patch.apply_to_document(&mut document)?;
+/// assert_eq!(document.to_string(), "\"Settings\"\n{\n    // between 0 and 1\n    \"volume\"  \"0.8\"\n    \"vsync\"\t\"1\"\n}\n");
+/// assert_eq!(document.value().get("Settings").and_then(|settings| settings.get("vsync")), Some(&Value::from("1")));
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Document {
+    text: String,
+    value: Value,
+}
+
+impl Document {
+    /// Returns what the document says, without its comments or layout
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Returns the text of the document
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Gives back the text of the document
+    pub fn into_string(self) -> String {
+        self.text
+    }
+}
+
+impl FromStr for Document {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(Document {
+            text: s.to_string(),
+            value: s.parse()?,
+        })
+    }
+}
+
+impl From<Value> for Document {
+    /// Writes out a value as a new document
+    fn from(value: Value) -> Self {
+        Document {
+            text: value.to_string(),
+            value,
+        }
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.text)
+    }
+}
+
+impl Patch {
+    /// Makes every change in this patch to `document`, in order, keeping the rest of its text as it was
+    ///
+    /// # Errors
+    ///
+    /// The same as [`apply`](#method.apply), and `document` will be left as it was.
+    pub fn apply_to_document(&self, document: &mut Document) -> Result<()> {
+        let mut text = document.text.clone();
+        let mut value = document.value.clone();
+        for op in &self.ops {
+            // checking the change on the value first means the text only has to worry about where things are
+            apply_op(&mut value, op)?;
+            edit_text(&mut text, op)?;
+        }
+        debug_assert_eq!(text.parse::<Value>().as_ref(), Ok(&value));
+        *document = Document { text, value };
+        Ok(())
+    }
+}
+
+/// Where an entry is in the text
+struct EntrySpan {
+    key: String,
+    /// From the start of the key to the end of the value
+    span: Span,
+    value: ValueSpan,
+}
+
+enum ValueSpan {
+    String(Span),
+    Group(GroupSpan),
+}
+
+/// Where a group and its entries are in the text
+///
+/// The whole document is a group too, which only has braces if it's a single group with no name.
+#[derive(Default)]
+struct GroupSpan {
+    open: Option<Span>,
+    close: Option<Span>,
+    entries: Vec<EntrySpan>,
+}
+
+impl GroupSpan {
+    fn parse(text: &str) -> Result<Self> {
+        // each open group, with its key and where that starts
+        let mut stack = vec![(String::new(), 0, GroupSpan::default())];
+        let mut key = None;
+        for item in Reader::new(text) {
+            let (event, span) = item?;
+            match event {
+                Event::Key(name) => key = Some((name.into_owned(), span.start)),
+                Event::Directive(name) => key = Some((format!("#{}", name), span.start)),
+                Event::Value(_) => {
+                    let (key, start) = key.take().unwrap_or_default();
+                    let entry = EntrySpan { key, span: Span { start, end: span.end }, value: ValueSpan::String(span) };
+                    stack.last_mut().expect("the root never closes").2.entries.push(entry);
+                }
+                Event::GroupStart if key.is_none() && stack.len() == 1 && stack[0].2.open.is_none() && stack[0].2.entries.is_empty() => {
+                    stack[0].2.open = Some(span);
+                }
+                Event::GroupStart => {
+                    let (key, start) = key.take().unwrap_or((String::new(), span.start));
+                    stack.push((key, start, GroupSpan { open: Some(span), ..GroupSpan::default() }));
+                }
+                Event::GroupEnd if stack.len() == 1 => stack[0].2.close = Some(span),
+                Event::GroupEnd => {
+                    let (key, start, mut group) = stack.pop().expect("the reader checks nesting");
+                    group.close = Some(span);
+                    let entry = EntrySpan { key, span: Span { start, end: span.end }, value: ValueSpan::Group(group) };
+                    stack.last_mut().expect("the root never closes").2.entries.push(entry);
+                }
+                // a condition after a value belongs to that entry, so it goes wherever the entry goes
+                Event::Conditional(_) if key.is_none() => {
+                    if let Some(entry) = stack.last_mut().expect("the root never closes").2.entries.last_mut() {
+                        entry.span.end = span.end;
+                    }
+                }
+                Event::Comment(_) | Event::Conditional(_) => {}
+            }
+        }
+        Ok(stack.pop().expect("the root never closes").2)
+    }
+
+    /// Finds the `n`th entry with a key
+    fn entry(&self, key: &str, n: usize) -> Option<&EntrySpan> {
+        self.entries.iter().filter(|entry| entry.key == key).nth(n)
+    }
+}
+
+/// Makes one change, which has already been checked against the value, to the text
+fn edit_text(text: &mut String, op: &PatchOp) -> Result<()> {
+    let path = op.path();
+    let root = GroupSpan::parse(text)?;
+    let mut segments = path_segments(path)?;
+    let (key, index) = match segments.pop() {
+        Some(last) => last,
+        None => {
+            // only a replace can have an empty path, and it replaces everything
+            if let PatchOp::Replace { value, .. } = op {
+                *text = value.to_string();
+            }
+            return Ok(());
+        }
+    };
+    let mut parent = &root;
+    for (key, index) in &segments {
+        parent = match parent.entry(key, index.unwrap_or(0)) {
+            Some(EntrySpan { value: ValueSpan::Group(group), .. }) => group,
+            _ => return Err(conflict(path, format!("{:?} doesn't exist", key))),
+        };
+    }
+    let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let (range, replacement) = match op {
+        PatchOp::Add { value, .. } => add_entry(text, parent, &key, value, newline),
+        PatchOp::Remove { .. } => {
+            let entry = parent.entry(&key, index.unwrap_or(0)).ok_or_else(|| conflict(path, "it doesn't exist"))?;
+            (removal_range(text, entry.span), String::new())
+        }
+        PatchOp::Replace { value, .. } => {
+            let entry = parent.entry(&key, index.unwrap_or(0)).ok_or_else(|| conflict(path, "it doesn't exist"))?;
+            let span = match &entry.value {
+                ValueSpan::String(span) => *span,
+                ValueSpan::Group(group) => Span {
+                    start: group.open.expect("groups under a key have braces").start,
+                    end: group.close.expect("groups under a key have braces").end,
+                },
+            };
+            (span.start..span.end, render_value(value, text, indent_of(text, entry.span.start), newline))
+        }
+    };
+    text.replace_range(range, &replacement);
+    Ok(())
+}
+
+/// Works out where a new entry goes at the end of `parent`, and what to put there
+fn add_entry(text: &str, parent: &GroupSpan, key: &str, value: &Value, newline: &str) -> (Range<usize>, String) {
+    let mut entry = Map::new();
+    entry.append(key.to_string(), value.clone());
+    let entry = Value::Group(entry);
+    // indent like the last entry if it's on a line of its own, or one level further in than the braces if not
+    let indent = match parent.entries.last() {
+        Some(last) if on_own_line(text, last.span.start) => indent_of(text, last.span.start).to_string(),
+        _ => match parent.close.or(parent.open) {
+            Some(brace) => format!("{}{}", indent_of(text, brace.start), indent_unit(text)),
+            None => String::new(),
+        },
+    };
+    let rendered = reindent(&entry.to_string(), text, &indent, newline);
+    match parent.close {
+        Some(close) if on_own_line(text, close.start) => {
+            let line_start = line_start(text, close.start);
+            (line_start..line_start, format!("{}{}{}", indent, rendered, newline))
+        }
+        Some(close) => (close.start..close.start, format!("{} ", rendered)),
+        None => {
+            let end = text.trim_end_matches(|c: char| c.is_ascii_whitespace()).len();
+            let trailing = &text[end..];
+            let separator = if end == 0 { "" } else { newline };
+            (end..text.len(), format!("{}{}{}{}", separator, indent, rendered, trailing))
+        }
+    }
+}
+
+/// Writes a value the way the `Writer` would, indented to match the rest of `text`
+fn render_value(value: &Value, text: &str, indent: &str, newline: &str) -> String {
+    let mut writer = Writer::new(Vec::new());
+    let written = match value {
+        Value::String(text) => writer.write_value(text),
+        Value::Group(group) => writer
+            .begin_unnamed_group()
+            .and_then(|()| write_entries(&mut writer, group))
+            .and_then(|()| writer.end_group()),
+    };
+    written.expect("writing to a Vec can't fail");
+    reindent(&String::from_utf8(writer.into_inner()).expect("the writer writes UTF-8"), text, indent, newline)
+}
+
+/// Indents what the `Writer` wrote the way `text` is indented, with every line after the first indented by `indent`
+fn reindent(written: &str, text: &str, indent: &str, newline: &str) -> String {
+    let unit = indent_unit(text);
+    let lines = written.split('\n').map(|line| {
+        let nested = line.trim_start_matches('\t');
+        format!("{}{}", unit.repeat(line.len() - nested.len()), nested)
+    });
+    lines.collect::<Vec<_>>().join(&format!("{}{}", newline, indent))
+}
+
+/// Finds what one level of indentation is in `text`, from the first line that's indented at all, or a tab if none are
+fn indent_unit(text: &str) -> &str {
+    text.lines()
+        .map(|line| &line[..line.len() - line.trim_start_matches([' ', '\t']).len()])
+        .find(|indent| !indent.is_empty())
+        .unwrap_or("\t")
+}
+
+/// Works out what to delete to remove an entry, which is its whole line if nothing else is on it but a comment
+fn removal_range(text: &str, span: Span) -> Range<usize> {
+    let rest_of_line = text[span.end..].find('\n').map_or(text.len(), |newline| span.end + newline + 1);
+    let after = text[span.end..rest_of_line].trim();
+    if on_own_line(text, span.start) && (after.is_empty() || after.starts_with("//")) {
+        let start = line_start(text, span.start);
+        if rest_of_line == text.len() && !text[span.end..].contains('\n') {
+            // the last line has no newline of its own to take, so take the one before it
+            let before = text[..start].trim_end_matches(['\n', '\r']).len();
+            return before..rest_of_line;
+        }
+        start..rest_of_line
+    } else {
+        let following = text[span.end..].len() - text[span.end..].trim_start_matches([' ', '\t']).len();
+        span.start..span.end + following
+    }
+}
+
+fn line_start(text: &str, offset: usize) -> usize {
+    text[..offset].rfind('\n').map_or(0, |newline| newline + 1)
+}
+
+/// Returns the whitespace at the start of the line `offset` is on
+fn indent_of(text: &str, offset: usize) -> &str {
+    let line = &text[line_start(text, offset)..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Checks whether there's nothing but whitespace before `offset` on its line
+fn on_own_line(text: &str, offset: usize) -> bool {
+    text[line_start(text, offset)..offset].trim().is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff, vdf};
+
+    const CONFIG: &str = "// machine settings\n\"Config\"\n{\n    \"volume\"    \"0.5\" // loud\n    \"legacy\"    \"1\"\n    \"paths\" { \"a\" \"x\" }\n    \"keys\"\n    {\n        \"dup\" \"1\"\n        \"dup\" \"2\"\n    }\n}\n";
+
+    #[test]
+    fn test_patch_document() {
+        let mut document: Document = CONFIG.parse().unwrap();
+        let old = document.value().clone();
+        let new = vdf! { "Config" { "volume" "0.8" "paths" { "a" "x" "b" "y" } "keys" { "dup" "1" } "fresh" { "k" "v" } } };
+        Patch::from(&diff(&old, &new)).apply_to_document(&mut document).unwrap();
+        assert_eq!(document.value(), &new);
+        assert_eq!(document.as_str(), concat!(
+            "// machine settings\n",
+            "\"Config\"\n",
+            "{\n",
+            "    \"volume\"    \"0.8\" // loud\n",
+            "    \"paths\" { \"a\" \"x\" \"b\"\t\"y\" }\n",
+            "    \"keys\"\n",
+            "    {\n",
+            "        \"dup\" \"1\"\n",
+            "    }\n",
+            "    \"fresh\"\n",
+            "    {\n",
+            "        \"k\"\t\"v\"\n",
+            "    }\n",
+            "}\n",
+        ));
+
+        // a conflict leaves everything as it was
+        let before = document.clone();
+        let conflicting = Patch { ops: vec![
+            PatchOp::Remove { path: "Config/volume".to_string(), old: None },
+            PatchOp::Remove { path: "Config/legacy".to_string(), old: None },
+        ] };
+        assert!(conflicting.apply_to_document(&mut document).is_err());
+        assert_eq!(document, before);
+    }
+
+    #[test]
+    fn test_patch_document_layouts() {
+        let mut document: Document = "\"a\" \"1\"\n\"b\" { \"c\" \"2\" }".parse().unwrap();
+        let patch = Patch { ops: vec![
+            PatchOp::Replace { path: "b".to_string(), old: None, value: vdf! { "d" "3" } },
+            PatchOp::Add { path: "e".to_string(), value: Value::from("4") },
+            PatchOp::Remove { path: "a".to_string(), old: Some(Value::from("1")) },
+        ] };
+        patch.apply_to_document(&mut document).unwrap();
+        assert_eq!(document.as_str(), "\"b\" {\n\t\"d\"\t\"3\"\n}\n\"e\"\t\"4\"");
+
+        // a group with no name around everything is the document, so entries go inside it
+        let mut document: Document = "{\r\n\t\"a\" \"1\"\r\n}\r\n".parse().unwrap();
+        Patch { ops: vec![PatchOp::Add { path: "b".to_string(), value: Value::from("2") }] }.apply_to_document(&mut document).unwrap();
+        assert_eq!(document.as_str(), "{\r\n\t\"a\" \"1\"\r\n\t\"b\"\t\"2\"\r\n}\r\n");
+
+        // a condition goes with the pair it's after
+        let mut document: Document = "\"a\" \"1\" [$X360]\n\"a\" \"2\" [!$X360]\n\"b\" \"3\"".parse().unwrap();
+        let patch = Patch { ops: vec![
+            PatchOp::Remove { path: "a[0]".to_string(), old: None },
+            PatchOp::Replace { path: "a".to_string(), old: None, value: Value::from("4") },
+        ] };
+        patch.apply_to_document(&mut document).unwrap();
+        assert_eq!(document.as_str(), "\"a\" \"4\" [!$X360]\n\"b\" \"3\"");
+
+        let mut document: Document = "".parse().unwrap();
+        Patch { ops: vec![PatchOp::Add { path: "a".to_string(), value: Value::from("1") }] }.apply_to_document(&mut document).unwrap();
+        assert_eq!(document.as_str(), "\"a\"\t\"1\"");
+    }
+}
@@ -0,0 +1,250 @@
+//! Overlay one document onto another
+
+use std::collections::HashMap;
+
+use crate::map::Map;
+
+use super::query::push_key;
+use super::Value;
+
+/// How [`Value::merge`](enum.Value.html#method.merge) combines two documents
+///
+/// Keys that only appear in the document being merged in are always added.
+/// When a key appears more than once, the first one merged in goes with the first one already there,
+/// the second with the second, and so on.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MergePolicy {
+    mode: Mode,
+    deletion_marker: Option<String>,
+    case_insensitive: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Mode {
+    Replace,
+    Recursive,
+    Append,
+}
+
+impl MergePolicy {
+    fn new(mode: Mode) -> Self {
+        MergePolicy {
+            mode,
+            deletion_marker: None,
+            case_insensitive: false,
+        }
+    }
+
+    /// Values replace the ones already there, even if they're both groups
+    pub fn replace() -> Self {
+        MergePolicy::new(Mode::Replace)
+    }
+
+    /// Groups are merged into groups with the same key, and everything else replaces what was there
+    ///
+    /// This is the default.
+    pub fn recursive() -> Self {
+        MergePolicy::new(Mode::Recursive)
+    }
+
+    /// Every entry is added after the ones already there, keeping both if the key repeats
+    pub fn append() -> Self {
+        MergePolicy::new(Mode::Append)
+    }
+
+    /// Sets a string value that deletes every entry with its key instead of being merged in
+    pub fn deletion_marker(mut self, marker: impl Into<String>) -> Self {
+        self.deletion_marker = Some(marker.into());
+        self
+    }
+
+    /// Sets whether keys are compared ignoring ASCII case, like the Source engine does
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    fn normalize(&self, key: &str) -> String {
+        if self.case_insensitive {
+            key.to_ascii_lowercase()
+        } else {
+            key.to_string()
+        }
+    }
+
+    fn is_deletion(&self, value: &Value) -> bool {
+        matches!((&self.deletion_marker, value), (Some(marker), Value::String(text)) if marker == text)
+    }
+}
+
+impl Default for MergePolicy {
+    fn default() -> Self {
+        MergePolicy::recursive()
+    }
+}
+
+/// Something a merge did, with the [`Query`](struct.Query.html)-style path it did it at
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MergeChange {
+    /// A new entry was added
+    Added(String),
+    /// An entry's value was replaced with a different one
+    Replaced(String),
+    /// An entry was deleted by a deletion marker
+    Removed(String),
+}
+
+impl Value {
+    /// Merges `other` into this value, and reports what changed
+    ///
+    /// If either value isn't a group, `other` just replaces this one.
+    ///
+    /// ```
+    /// use vdf_serde::{vdf, MergeChange, MergePolicy};
+    ///
+    /// let mut base = vdf! { "Settings" { "volume" "0.5" "Video" { "width" "1920" "height" "1080" } "legacy" "1" } };
+    /// let overlay = vdf! { "Settings" { "Video" { "width" "2560" } "legacy" "__delete__" } };
+    /// let changes = base.merge(overlay, &MergePolicy::recursive().deletion_marker("__delete__"));
+    /// assert_eq!(base, vdf! { "Settings" { "volume" "0.5" "Video" { "width" "2560" "height" "1080" } } });
+    /// assert_eq!(changes, [
+    ///     MergeChange::Replaced("Settings/Video/width".to_string()),
+    ///     MergeChange::Removed("Settings/legacy".to_string()),
+    /// ]);
+    /// ```
+    pub fn merge(&mut self, other: Value, policy: &MergePolicy) -> Vec<MergeChange> {
+        let mut changes = Vec::new();
+        match (&mut *self, other) {
+            (Value::Group(target), Value::Group(other)) => merge_group(target, other, policy, "", &mut changes),
+            (target, other) => {
+                if *target != other {
+                    *target = other;
+                    changes.push(MergeChange::Replaced(String::new()));
+                }
+            }
+        }
+        changes
+    }
+}
+
+fn merge_group(target: &mut Map<String, Value>, other: Map<String, Value>, policy: &MergePolicy, path: &str, changes: &mut Vec<MergeChange>) {
+    let mut seen = HashMap::new();
+    for (key, value) in other {
+        let normalized = policy.normalize(&key);
+        let matching = |target: &Map<String, Value>| {
+            target.keys().enumerate().filter(|(_, other)| policy.normalize(other) == normalized).map(|(i, _)| i).collect::<Vec<_>>()
+        };
+        if policy.is_deletion(&value) {
+            for position in matching(target) {
+                changes.push(MergeChange::Removed(entry_path(path, target, position)));
+            }
+            target.retain(|other, _| policy.normalize(other) != normalized);
+            continue;
+        }
+
+        let existing = match policy.mode {
+            Mode::Append => None,
+            Mode::Replace | Mode::Recursive => {
+                let n = seen.entry(normalized.clone()).or_insert(0);
+                *n += 1;
+                matching(target).get(*n - 1).copied()
+            }
+        };
+        match existing {
+            Some(position) => {
+                let entry = entry_path(path, target, position);
+                let existing = target.iter_mut().nth(position).map(|(_, existing)| existing).expect("the position was just found");
+                match (existing, value) {
+                    (Value::Group(existing), Value::Group(value)) if policy.mode == Mode::Recursive => {
+                        merge_group(existing, value, policy, &entry, changes);
+                    }
+                    (existing, value) => {
+                        if *existing != value {
+                            *existing = value;
+                            changes.push(MergeChange::Replaced(entry));
+                        }
+                    }
+                }
+            }
+            None => {
+                target.append(key, value);
+                changes.push(MergeChange::Added(entry_path(path, target, target.len() - 1)));
+            }
+        }
+    }
+}
+
+/// Builds the path to the entry at `position` in `group` with the key as it's spelled there, like a
+/// [`Query`](struct.Query.html) match's path, so only keys spelled exactly the same way more than once get an `[n]`
+fn entry_path(path: &str, group: &Map<String, Value>, position: usize) -> String {
+    let key = group.keys().nth(position).expect("the entry is in the group");
+    let occurrence = group.keys().take(position).filter(|other| *other == key).count();
+    let total = group.keys().filter(|other| *other == key).count();
+    let mut path = path.to_string();
+    push_key(&mut path, key, if total > 1 { Some(occurrence) } else { None });
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vdf;
+
+    #[test]
+    fn test_merge_policies() {
+        let base = vdf! { "a" "1" "Group" { "x" "1" } "dup" "1" "dup" "2" };
+        let overlay = vdf! { "A" "2" "Group" { "y" "2" } "dup" "3" "dup" "2" "dup" "4" };
+
+        let mut merged = base.clone();
+        let changes = merged.merge(overlay.clone(), &MergePolicy::replace());
+        assert_eq!(merged, vdf! { "a" "1" "Group" { "y" "2" } "dup" "3" "dup" "2" "A" "2" "dup" "4" });
+        assert_eq!(changes, [
+            MergeChange::Added("A".to_string()),
+            MergeChange::Replaced("Group".to_string()),
+            MergeChange::Replaced("dup[0]".to_string()),
+            MergeChange::Added("dup[2]".to_string()),
+        ]);
+
+        let mut merged = base.clone();
+        let changes = merged.merge(overlay.clone(), &MergePolicy::recursive().case_insensitive(true));
+        assert_eq!(merged, vdf! { "a" "2" "Group" { "x" "1" "y" "2" } "dup" "3" "dup" "2" "dup" "4" });
+        assert_eq!(changes, [
+            MergeChange::Replaced("a".to_string()),
+            MergeChange::Added("Group/y".to_string()),
+            MergeChange::Replaced("dup[0]".to_string()),
+            MergeChange::Added("dup[2]".to_string()),
+        ]);
+
+        let mut merged = base.clone();
+        let changes = merged.merge(overlay, &MergePolicy::append());
+        assert_eq!(merged.as_group().unwrap().len(), 9);
+        assert_eq!(changes[1], MergeChange::Added("Group[1]".to_string()));
+
+        let mut merged = base.clone();
+        let changes = merged.merge(vdf! { "dup" "-" }, &MergePolicy::default().deletion_marker("-"));
+        assert_eq!(merged, vdf! { "a" "1" "Group" { "x" "1" } });
+        assert_eq!(changes, [MergeChange::Removed("dup[0]".to_string()), MergeChange::Removed("dup[1]".to_string())]);
+
+        // paths use the keys as they're spelled in the target, numbered like a query's are
+        let mut merged = vdf! { "dup" "1" "Dup" "2" "g" { "k" "1" "K" "2" } };
+        let overlay = vdf! { "DUP" "-" "G" { "K" "3" "k" "4" } };
+        let changes = merged.merge(overlay, &MergePolicy::recursive().case_insensitive(true).deletion_marker("-"));
+        assert_eq!(merged, vdf! { "g" { "k" "3" "K" "4" } });
+        assert_eq!(changes, [
+            MergeChange::Removed("dup".to_string()),
+            MergeChange::Removed("Dup".to_string()),
+            MergeChange::Replaced("g/k".to_string()),
+            MergeChange::Replaced("g/K".to_string()),
+        ]);
+        let mut merged = vdf! { "a" "1" "a" "2" };
+        let changes = merged.merge(vdf! { "A" "3" "A" "4" "A" "5" }, &MergePolicy::replace().case_insensitive(true));
+        assert_eq!(changes, [
+            MergeChange::Replaced("a[0]".to_string()),
+            MergeChange::Replaced("a[1]".to_string()),
+            MergeChange::Added("A".to_string()),
+        ]);
+
+        let mut leaf = Value::from("old");
+        assert_eq!(leaf.merge(base.clone(), &MergePolicy::default()), [MergeChange::Replaced(String::new())]);
+        assert_eq!(leaf, base);
+    }
+}
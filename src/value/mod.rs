@@ -0,0 +1,389 @@
+//! A VDF value of any shape, for data you can't write a struct for
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::error::{Error, Result};
+use crate::map::Map;
+use crate::reader::{Event, Reader};
+use crate::writer::Writer;
+
+mod de;
+mod diff;
+mod document;
+mod merge;
+mod patch;
+mod query;
+mod ser;
+
+pub use de::from_value;
+pub use diff::{diff, Diff, DiffEntry};
+pub use document::Document;
+pub use merge::{MergeChange, MergePolicy};
+pub use patch::{Patch, PatchOp};
+pub use query::{Match, Query};
+pub use ser::to_value;
+
+/// Any VDF value, which is either a string or a group of key-value pairs
+///
+/// A whole VDF document is a group of its top-level pairs, so [`FromStr`](#impl-FromStr) gives back a `Group`,
+/// and formatting a `Group` with `Display` writes its entries without braces around them.
+/// Directives like `#base` are kept as keys starting with `#`.
+///
+/// ```
+/// use vdf_serde::Value;
+///
+/// let value: Value = r#""AppState" { "appid" "620" "name" "Portal 2" }"#.parse()?;
+/// assert_eq!(value.get("AppState").and_then(|app| app.get("appid")).and_then(|id| id.as_str()), Some("620"));
+/// assert_eq!(value.to_string(), "\"AppState\"\n{\n\t\"appid\"\t\"620\"\n\t\"name\"\t\"Portal 2\"\n}");
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Value {
+    /// A quoted string, which is what every number and bool is too
+    String(String),
+    /// A group of key-value pairs in braces, which can repeat keys
+    Group(Map<String, Value>),
+}
+
+impl Value {
+    /// Returns the string, if this is a string
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(text) => Some(text),
+            Value::Group(_) => None,
+        }
+    }
+
+    /// Returns the group, if this is a group
+    pub fn as_group(&self) -> Option<&Map<String, Value>> {
+        match self {
+            Value::String(_) => None,
+            Value::Group(group) => Some(group),
+        }
+    }
+
+    /// Returns the group mutably, if this is a group
+    pub fn as_group_mut(&mut self) -> Option<&mut Map<String, Value>> {
+        match self {
+            Value::String(_) => None,
+            Value::Group(group) => Some(group),
+        }
+    }
+
+    /// Returns whether this is a string
+    pub fn is_str(&self) -> bool {
+        self.as_str().is_some()
+    }
+
+    /// Returns whether this is a group
+    pub fn is_group(&self) -> bool {
+        self.as_group().is_some()
+    }
+
+    /// Looks up the first value under `key`, if this is a group
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_group().and_then(|group| group.get(key))
+    }
+
+    /// Looks up the first value under `key` mutably, if this is a group
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.as_group_mut().and_then(|group| group.get_mut(key))
+    }
+
+    /// Writes this as a VDF document, the same way `Display` does
+    pub(crate) fn write_document<W: std::io::Write>(&self, writer: &mut Writer<W>) -> Result<()> {
+        match self {
+            Value::String(text) => writer.write_value(text),
+            Value::Group(group) => write_entries(writer, group),
+        }
+    }
+}
+
+pub(crate) fn write_entries<W: std::io::Write>(writer: &mut Writer<W>, group: &Map<String, Value>) -> Result<()> {
+    for (key, value) in group {
+        match value {
+            Value::String(text) => writer.write_pair(key, text)?,
+            Value::Group(inner) => {
+                writer.begin_group(key)?;
+                write_entries(writer, inner)?;
+                writer.end_group()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Default for Value {
+    fn default() -> Self {
+        Value::Group(Map::new())
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let mut writer = Writer::new(Vec::new());
+        self.write_document(&mut writer).map_err(|_| fmt::Error)?;
+        formatter.write_str(&String::from_utf8_lossy(&writer.into_inner()))
+    }
+}
+
+impl FromStr for Value {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // each open group, with the key it goes under in its parent
+        let mut stack = vec![(String::new(), Map::new())];
+        let mut key = None;
+        // a document that's a single group in braces with no name in front of it is that group
+        let (mut braced, mut closed) = (false, false);
+        for item in Reader::new(s) {
+            let (event, _span) = item?;
+            if closed && !matches!(event, Event::Comment(_) | Event::Conditional(_)) {
+                return Err(Error::LateEOF);
+            }
+            match event {
+                Event::Key(text) => key = Some(text.into_owned()),
+                Event::Directive(text) => key = Some(format!("#{}", text)),
+                Event::Value(text) => {
+                    let key = key.take().unwrap_or_default();
+                    stack.last_mut().expect("the root never closes").1.append(key, Value::String(text.into_owned()));
+                }
+                Event::GroupStart if !braced && key.is_none() && stack.len() == 1 && stack[0].1.is_empty() => braced = true,
+                Event::GroupStart => stack.push((key.take().unwrap_or_default(), Map::new())),
+                Event::GroupEnd if stack.len() == 1 => closed = true,
+                Event::GroupEnd => {
+                    let (key, group) = stack.pop().expect("the reader checks nesting");
+                    stack.last_mut().expect("the root never closes").1.append(key, Value::Group(group));
+                }
+                Event::Comment(_) | Event::Conditional(_) => {}
+            }
+        }
+        let (_, root) = stack.pop().expect("the reader checks nesting");
+        Ok(Value::Group(root))
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Value::String(text)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Value::String(text.to_string())
+    }
+}
+
+impl From<char> for Value {
+    fn from(c: char) -> Self {
+        Value::String(c.to_string())
+    }
+}
+
+impl From<bool> for Value {
+    /// Bools become `"1"` or `"0"`, like the serializer writes them by default
+    fn from(b: bool) -> Self {
+        Value::String(if b { "1" } else { "0" }.to_string())
+    }
+}
+
+impl From<Map<String, Value>> for Value {
+    fn from(group: Map<String, Value>) -> Self {
+        Value::Group(group)
+    }
+}
+
+macro_rules! from_display {
+    ($($type:ty)*) => {
+        $(
+            impl From<$type> for Value {
+                fn from(v: $type) -> Self {
+                    Value::String(v.to_string())
+                }
+            }
+        )*
+    };
+}
+
+from_display! {
+    i8 i16 i32 i64 i128 isize
+    u8 u16 u32 u64 u128 usize
+    f32 f64
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Value::String(text) => serializer.serialize_str(text),
+            Value::Group(group) => group.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct ValueVisitor;
+
+        impl<'de> Visitor<'de> for ValueVisitor {
+            type Value = Value;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a string or a group")
+            }
+
+            fn visit_bool<E: serde::de::Error>(self, v: bool) -> std::result::Result<Value, E> {
+                Ok(Value::from(v))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> std::result::Result<Value, E> {
+                Ok(Value::from(v))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> std::result::Result<Value, E> {
+                Ok(Value::from(v))
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> std::result::Result<Value, E> {
+                Ok(Value::from(v))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> std::result::Result<Value, E> {
+                Ok(Value::from(v))
+            }
+
+            fn visit_string<E: serde::de::Error>(self, v: String) -> std::result::Result<Value, E> {
+                Ok(Value::String(v))
+            }
+
+            fn visit_unit<E: serde::de::Error>(self) -> std::result::Result<Value, E> {
+                Ok(Value::from(""))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> std::result::Result<Value, A::Error> {
+                let mut group = Map::with_capacity(access.size_hint().unwrap_or(0));
+                while let Some((key, value)) = access.next_entry()? {
+                    group.append(key, value);
+                }
+                Ok(Value::Group(group))
+            }
+
+            /// A group with repeated keys comes as a list of one-entry maps, from JSON and from `deserialize_any`
+            fn visit_seq<A: SeqAccess<'de>>(self, mut access: A) -> std::result::Result<Value, A::Error> {
+                let mut group = Map::with_capacity(access.size_hint().unwrap_or(0));
+                while let Some(entries) = access.next_element::<Map<String, Value>>()? {
+                    group.extend(entries);
+                }
+                Ok(Value::Group(group))
+            }
+        }
+
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let input = "#base \"base.vdf\"\n\"Root\"\n{\n\t// ignored\n\t\"a\"\t\"1\"\n\t\"a\"\t\"2\"\n\t\"empty\"\n\t{\n\t}\n}";
+        let value = input.parse::<Value>().unwrap();
+        let root = value.get("Root").unwrap();
+        assert_eq!(root.as_group().unwrap().get_all("a").collect::<Vec<_>>(), [&Value::from(1), &Value::from(2)]);
+        assert_eq!(root.get("empty"), Some(&Value::default()));
+        assert_eq!(value.get("#base").and_then(Value::as_str), Some("base.vdf"));
+        assert_eq!(value.to_string(), input.replace("#base ", "\"#base\"\t").replace("\t// ignored\n", ""));
+
+        assert_eq!("\"a\" {".parse::<Value>(), Err(Error::EarlyEOF));
+        assert_eq!(Value::from("lone").to_string(), "\"lone\"");
+    }
+
+    #[test]
+    fn test_deserialize() {
+        use crate::from_str;
+
+        let input = "#base \"base.vdf\"\n\"Root\"\n{\n\t\"a\"\t\"1\"\n\t\"a\"\t{ \"x\" \"y\" }\n}";
+        assert_eq!(from_str::<Value>(input), input.parse());
+        assert_eq!(from_str::<Value>("\"lone\""), Ok(Value::from("lone")));
+        assert_eq!(from_str::<Value>(""), Ok(Value::default()));
+
+        // a group with no name around the whole document is the document
+        let braced = "\n{\n\t\"a\"\t\"1\"\n}";
+        assert_eq!(from_str::<Value>(braced).unwrap().to_string(), "\"a\"\t\"1\"");
+        assert_eq!(braced.parse::<Value>().unwrap().to_string(), "\"a\"\t\"1\"");
+        assert_eq!(from_str::<Value>("{ \"a\" \"1\" } \"b\" \"2\""), Err(Error::LateEOF));
+        assert_eq!("{ \"a\" \"1\" } \"b\" \"2\"".parse::<Value>(), Err(Error::LateEOF));
+    }
+
+    #[test]
+    fn test_to_and_from_value() {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Branch { Public, Beta }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Marker;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Test {
+            flag: bool,
+            ratio: f32,
+            pair: (u8, char),
+            branch: Branch,
+            marker: Marker,
+            apps: std::collections::BTreeMap<u32, String>,
+            note: Option<String>,
+        }
+
+        let test = Test {
+            flag: true,
+            ratio: 0.5,
+            pair: (7, 'x'),
+            branch: Branch::Beta,
+            marker: Marker,
+            apps: vec![(440, "Team Fortress 2".to_string())].into_iter().collect(),
+            note: Some("hi".to_string()),
+        };
+        let value = to_value(&test).unwrap();
+        assert_eq!(value, crate::vdf! {
+            "flag" "1"
+            "ratio" "0.5"
+            "pair" { "0" "7" "1" "x" }
+            "branch" "Beta"
+            "marker" {}
+            "apps" { "440" "Team Fortress 2" }
+            "note" "hi"
+        });
+        assert_eq!(from_value::<Test>(value).unwrap(), test);
+
+        assert_eq!(from_value::<(u8, u8)>(Value::from("1 2")), Ok((1, 2)));
+        assert_eq!(from_value::<(u8, u8)>(crate::vdf! { "0" "1", "1" "2" }), Ok((1, 2)));
+        assert!(from_value::<(u8, u8)>(crate::vdf! { "7" "1", "1" "2" }).is_err());
+        assert_eq!(from_value::<u8>(crate::vdf! {}), Err(Error::Expected("string", "group".to_string())));
+
+        // sequences are a key repeated once for each element, wherever its entries are
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Solid {
+            id: u32,
+            side: Vec<u32>,
+        }
+        let value = crate::vdf! { "side" "1" "id" "7" "side" "2" };
+        assert_eq!(from_value::<Solid>(value.clone()), Ok(Solid { id: 7, side: vec![1, 2] }));
+        assert_eq!(to_value(&Solid { id: 7, side: vec![1, 2] }), Ok(crate::vdf! { "id" "7" "side" "1" "side" "2" }));
+        assert_eq!(to_value(&vec![1, 2]), Err(Error::UnsupportedType("seq")));
+
+        // something that takes whatever it's given sees the same thing as when reading text
+        let text = "\"side\" \"1\" \"id\" \"7\" \"side\" { \"a\" \"b\" }";
+        let from_text = crate::from_str::<serde_json::Value>(text).unwrap();
+        assert_eq!(from_value::<serde_json::Value>(text.parse().unwrap()).unwrap(), from_text);
+        assert_eq!(from_value::<Value>(value.clone()), Ok(value));
+        assert!(from_value::<u8>(Value::from("300")).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+//! Change a document with a list of path-addressed operations
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::map::Map;
+
+use super::diff::{Diff, DiffEntry};
+use super::query::Query;
+use super::Value;
+
+/// One change in a [`Patch`](struct.Patch.html)
+///
+/// Paths are like [`Query`](struct.Query.html) paths without wildcards, where a key with no `[n]`
+/// means the first entry with that key. An `old` value, if there is one, has to match what's
+/// there before the change is made.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    /// Adds an entry to the end of its group, which is a conflict if the entry is already there
+    Add {
+        /// Where the entry goes
+        path: String,
+        /// What it is
+        value: Value,
+    },
+    /// Removes an entry
+    Remove {
+        /// Which entry
+        path: String,
+        /// What it has to be beforehand
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        old: Option<Value>,
+    },
+    /// Replaces an entry's value
+    Replace {
+        /// Which entry
+        path: String,
+        /// What it has to be beforehand
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        old: Option<Value>,
+        /// What it is afterwards
+        value: Value,
+    },
+}
+
+/// A list of changes to make to a [`Value`](enum.Value.html), which serializes as a list like a JSON Patch does
+///
+/// A patch made from a [`Diff`](struct.Diff.html) turns the old value into the new one, except that
+/// it doesn't move entries around, and added entries go at the end of their group.
+///
+/// ```
+/// use vdf_serde::{vdf, Patch};
+///
+/// let old = vdf! { "Settings" { "volume" "0.5" "legacy" "1" } };
+/// let new = vdf! { "Settings" { "volume" "0.8" "vsync" "1" } };
+/// let patch = Patch::from(&vdf_serde::diff(&old, &new));
+///
+/// let mut value = old.clone();
+/// patch.apply(&mut value)?;
+/// assert_eq!(value, new);
+/// // applying it again doesn't work, since the volume isn't 0.5 any more
+/// assert!(patch.apply(&mut value).is_err());
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Patch {
+    /// The changes, in the order they're made
+    pub ops: Vec<PatchOp>,
+}
+
+impl Patch {
+    /// Makes every change in this patch to `value`, in order
+    ///
+    /// # Errors
+    ///
+    /// If a path is invalid, or has a wildcard in it, or anything doesn't match what the patch expects,
+    /// an error will be returned and `value` will be left as it was.
+    pub fn apply(&self, value: &mut Value) -> Result<()> {
+        let mut patched = value.clone();
+        for op in &self.ops {
+            apply_op(&mut patched, op)?;
+        }
+        *value = patched;
+        Ok(())
+    }
+}
+
+impl From<&Diff> for Patch {
+    fn from(diff: &Diff) -> Self {
+        let mut replaces = Vec::new();
+        let mut removes = Vec::new();
+        let mut adds = Vec::new();
+        for entry in &diff.entries {
+            match entry.clone() {
+                DiffEntry::Changed { path, old, new } => replaces.push(PatchOp::Replace { path, old: Some(old), value: new }),
+                DiffEntry::Removed { path, value } => removes.push(PatchOp::Remove { path, old: Some(value) }),
+                DiffEntry::Added { path, value } => adds.push(PatchOp::Add { path, value }),
+                DiffEntry::Moved { .. } => {}
+            }
+        }
+        // removing the last of a repeated key first keeps the indices of the others the same
+        removes.reverse();
+        Patch {
+            ops: replaces.into_iter().chain(removes).chain(adds).collect(),
+        }
+    }
+}
+
+fn check_old(path: &str, old: &Option<Value>, found: &Value) -> Result<()> {
+    match old {
+        Some(old) if old != found => Err(conflict(path, format!("expected {:?}, found {:?}", old, found))),
+        _ => Ok(()),
+    }
+}
+
+impl PatchOp {
+    /// Returns the path of the entry this changes
+    pub fn path(&self) -> &str {
+        match self {
+            PatchOp::Add { path, .. } | PatchOp::Remove { path, .. } | PatchOp::Replace { path, .. } => path,
+        }
+    }
+}
+
+/// Splits a patch path into its keys and their indices, which can't be wildcards
+pub(super) fn path_segments(path: &str) -> Result<Vec<(String, Option<usize>)>> {
+    Query::new(path)?
+        .segments()
+        .iter()
+        .map(|segment| match &segment.key {
+            Some(key) => Ok((key.clone(), segment.index)),
+            None => Err(Error::StringParse(format!("invalid patch path {:?}: wildcards aren't allowed", path))),
+        })
+        .collect()
+}
+
+pub(super) fn conflict(path: &str, reason: impl Into<String>) -> Error {
+    Error::PatchConflict {
+        path: path.to_string(),
+        reason: reason.into(),
+    }
+}
+
+pub(super) fn apply_op(root: &mut Value, op: &PatchOp) -> Result<()> {
+    let path = op.path();
+    let mut segments = path_segments(path)?;
+
+    let (key, index) = match segments.pop() {
+        Some(last) => last,
+        None => {
+            return match op {
+                PatchOp::Replace { old, value, .. } => {
+                    check_old(path, old, root)?;
+                    *root = value.clone();
+                    Ok(())
+                }
+                _ => Err(conflict(path, "the root can only be replaced")),
+            }
+        }
+    };
+    let group = parent_group(root, &segments, path)?;
+    let n = index.unwrap_or(0);
+    match op {
+        PatchOp::Add { value, .. } => {
+            let count = group.get_all(&key).count();
+            if count > n {
+                return Err(conflict(path, "it already exists"));
+            } else if count < n {
+                return Err(conflict(path, format!("there are only {} entries with that key before it", count)));
+            }
+            group.append(key, value.clone());
+        }
+        PatchOp::Remove { old, .. } => {
+            let found = group.get_all(&key).nth(n).ok_or_else(|| conflict(path, "it doesn't exist"))?;
+            check_old(path, old, found)?;
+            let mut seen = 0;
+            group.retain(|other, _| {
+                if *other != key {
+                    return true;
+                }
+                seen += 1;
+                seen - 1 != n
+            });
+        }
+        PatchOp::Replace { old, value, .. } => {
+            let found = group.get_all_mut(&key).nth(n).ok_or_else(|| conflict(path, "it doesn't exist"))?;
+            check_old(path, old, found)?;
+            *found = value.clone();
+        }
+    }
+    Ok(())
+}
+
+/// Finds the group that the last part of a path is in
+fn parent_group<'a>(root: &'a mut Value, segments: &[(String, Option<usize>)], path: &str) -> Result<&'a mut Map<String, Value>> {
+    let mut current = root;
+    for (key, index) in segments {
+        current = current
+            .as_group_mut()
+            .and_then(|group| group.iter_mut().filter(|(other, _)| *other == key).nth(index.unwrap_or(0)))
+            .map(|(_, value)| value)
+            .ok_or_else(|| conflict(path, format!("{:?} doesn't exist", key)))?;
+    }
+    current.as_group_mut().ok_or_else(|| conflict(path, "its parent isn't a group"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff, vdf};
+
+    #[test]
+    fn test_patch() {
+        let old = vdf! { "dup" "a" "dup" "b" "dup" "c" "group" { "k" "v" } };
+        let new = vdf! { "dup" "a" "group" { "k" "w" "n" { "deep" "1" } } };
+        let patch = Patch::from(&diff(&old, &new));
+        let mut value = old.clone();
+        patch.apply(&mut value).unwrap();
+        assert_eq!(value, new);
+
+        let json = serde_json::to_string(&patch).unwrap();
+        assert!(json.starts_with(r#"[{"op":"replace","path":"group/k","old":"v","value":"w"}"#));
+        assert_eq!(serde_json::from_str::<Patch>(&json).unwrap(), patch);
+
+        let unchecked = serde_json::from_str::<Patch>(r#"[{"op": "remove", "path": "dup[1]"}, {"op": "add", "path": "dup[2]", "value": "d"}]"#).unwrap();
+        let mut value = old.clone();
+        unchecked.apply(&mut value).unwrap();
+        assert_eq!(value, vdf! { "dup" "a" "dup" "c" "group" { "k" "v" } "dup" "d" });
+
+        let mut value = old.clone();
+        let conflicting = Patch { ops: vec![
+            PatchOp::Replace { path: "dup[2]".to_string(), old: None, value: Value::from("z") },
+            PatchOp::Add { path: "group/k".to_string(), value: Value::from("x") },
+        ] };
+        assert_eq!(conflicting.apply(&mut value), Err(Error::PatchConflict { path: "group/k".to_string(), reason: "it already exists".to_string() }));
+        assert_eq!(value, old);
+        let wildcard = Patch { ops: vec![PatchOp::Remove { path: "*".to_string(), old: None }] };
+        assert!(matches!(wildcard.apply(&mut value), Err(Error::StringParse(_))));
+    }
+}
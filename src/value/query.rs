@@ -0,0 +1,275 @@
+//! Find values by their path through a document
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::map::Map;
+
+use super::Value;
+
+/// A slash-separated path to pick values out of a [`Value`](enum.Value.html)
+///
+/// Each part of the path is one of:
+///
+/// - a key, like `AppState`, which matches every entry with that key
+/// - `*`, which matches every entry
+/// - either of those followed by `[n]`, which only matches the `n`th entry (counting from 0) among the ones with the same key,
+///   or among the ones the key matches if case is being ignored
+///
+/// Backslashes escape `/`, `*`, `[`, and `\` in keys.
+///
+/// ```
+/// use vdf_serde::{Query, Value};
+///
+/// let manifest: Value = r#""AppState" {
+///     "InstalledDepots" {
+///         "228981" { "manifest" "7613356809904826842" }
+///         "228982" { "manifest" "6413394087650432851" }
+///     }
+/// }"#.parse()?;
+/// let found = Query::new("appstate/installeddepots/*/manifest")?.case_insensitive(true).matches(&manifest);
+/// let paths = found.iter().map(|found| found.path.as_str()).collect::<Vec<_>>();
+/// assert_eq!(paths, ["AppState/InstalledDepots/228981/manifest", "AppState/InstalledDepots/228982/manifest"]);
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Query {
+    segments: Vec<Segment>,
+    case_insensitive: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Segment {
+    /// `None` for `*`
+    pub(crate) key: Option<String>,
+    pub(crate) index: Option<usize>,
+}
+
+/// A value found by a [`Query`](struct.Query.html)
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Match<'a> {
+    /// The full path to the value, with the keys as they're spelled in the document,
+    /// and an `[n]` after any key that's spelled exactly the same more than once in its group,
+    /// so that it leads back to the same value without ignoring case
+    pub path: String,
+    /// The value itself
+    pub value: &'a Value,
+}
+
+impl Query {
+    /// Parses a path
+    ///
+    /// # Errors
+    ///
+    /// If a `[n]` isn't a number, or isn't at the end of its part of the path, an error will be returned.
+    pub fn new(path: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut chars = path.chars();
+        let mut key = String::new();
+        let mut wildcard = false;
+        let mut index = None;
+        loop {
+            let c = chars.next();
+            if index.is_some() && !matches!(c, None | Some('/')) {
+                return Err(Error::StringParse(format!("invalid path {:?}: [n] has to come last", path)));
+            }
+            match c {
+                None | Some('/') => {
+                    if wildcard || !key.is_empty() || index.is_some() {
+                        segments.push(Segment {
+                            key: if wildcard { None } else { Some(std::mem::take(&mut key)) },
+                            index: index.take(),
+                        });
+                    }
+                    wildcard = false;
+                    if c.is_none() {
+                        break;
+                    }
+                }
+                Some('\\') => key.extend(chars.next()),
+                Some('*') if key.is_empty() && !wildcard => wildcard = true,
+                Some('[') => {
+                    let rest = chars.as_str();
+                    let end = rest.find(']').ok_or_else(|| Error::StringParse(format!("invalid path {:?}: unclosed [", path)))?;
+                    let n = rest[..end].parse().map_err(|err| Error::StringParse(format!("invalid path {:?}: {}", path, err)))?;
+                    index = Some(n);
+                    chars = rest[end + 1..].chars();
+                }
+                Some(_) if wildcard => return Err(Error::StringParse(format!("invalid path {:?}: * has to be a whole key", path))),
+                Some(c) => key.push(c),
+            }
+        }
+        Ok(Query {
+            segments,
+            case_insensitive: false,
+        })
+    }
+
+    /// Sets whether keys are compared ignoring ASCII case, like the Source engine does
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    pub(crate) fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Finds every value this path leads to in `value`, in document order
+    pub fn matches<'a>(&self, value: &'a Value) -> Vec<Match<'a>> {
+        let mut found = vec![Match { path: String::new(), value }];
+        for segment in &self.segments {
+            found = found
+                .into_iter()
+                .filter_map(|parent| parent.value.as_group().map(|group| (parent.path, group)))
+                .flat_map(|(path, group)| self.step(segment, &path, group))
+                .collect();
+        }
+        found
+    }
+
+    /// Matches one part of the path against the entries of one group
+    fn step<'a>(&self, segment: &Segment, path: &str, group: &'a Map<String, Value>) -> Vec<Match<'a>> {
+        // indices in the paths that come out count keys spelled exactly the same, so they work without ignoring case
+        let mut totals = HashMap::new();
+        for (key, _) in group {
+            *totals.entry(key.as_str()).or_insert(0) += 1;
+        }
+        let mut seen = HashMap::new();
+        let mut matched = 0;
+        let mut found = Vec::new();
+        for (key, value) in group {
+            let exact = seen.entry(key.as_str()).or_insert(0);
+            *exact += 1;
+            let exact = *exact - 1;
+            let occurrence = match &segment.key {
+                Some(wanted) if self.key_eq(wanted, key) => {
+                    matched += 1;
+                    matched - 1
+                }
+                Some(_) => continue,
+                None => exact,
+            };
+            if matches!(segment.index, Some(index) if index != occurrence) {
+                continue;
+            }
+            let mut path = path.to_string();
+            push_key(&mut path, key, if totals[key.as_str()] > 1 { Some(exact) } else { None });
+            found.push(Match { path, value });
+        }
+        found
+    }
+
+    fn key_eq(&self, a: &str, b: &str) -> bool {
+        if self.case_insensitive {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    }
+}
+
+/// Adds a key to the end of a path, escaped so that it parses back as a `Query`
+pub(crate) fn push_key(path: &mut String, key: &str, index: Option<usize>) {
+    if !path.is_empty() {
+        path.push('/');
+    }
+    path.push_str(&escape_key(key));
+    if let Some(index) = index {
+        path.push_str(&format!("[{}]", index));
+    }
+}
+
+fn escape_key(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for c in key.chars() {
+        if matches!(c, '/' | '*' | '[' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Query::new(s)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let parts = self
+            .segments
+            .iter()
+            .map(|segment| {
+                let key = segment.key.as_deref().map_or_else(|| "*".to_string(), escape_key);
+                match segment.index {
+                    Some(index) => format!("{}[{}]", key, index),
+                    None => key,
+                }
+            })
+            .collect::<Vec<_>>();
+        let path = parts.join("/");
+        formatter.write_str(&path)
+    }
+}
+
+impl Value {
+    /// Finds every value at `path`, which is parsed as a [`Query`](struct.Query.html) that matches case exactly
+    ///
+    /// ```
+    /// use vdf_serde::vdf;
+    ///
+    /// let value = vdf! { "Proxies" { "Sine" { "resultVar" "$alpha" } "Sine" { "resultVar" "$color" } } };
+    /// let found = value.query("Proxies/Sine[1]/resultVar")?;
+    /// assert_eq!(found[0].value.as_str(), Some("$color"));
+    /// assert_eq!(found[0].path, "Proxies/Sine[1]/resultVar");
+    /// # Ok::<(), vdf_serde::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If `path` isn't a valid path, an error will be returned.
+    pub fn query(&self, path: &str) -> Result<Vec<Match<'_>>> {
+        Ok(Query::new(path)?.matches(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let value = crate::vdf! {
+            "Root" {
+                "a/b" "slash"
+                "dup" "0"
+                "Dup" "1"
+                "dup" "2"
+                "group" { "leaf" "x" }
+            }
+        };
+        let paths = |query: Query| query.matches(&value).into_iter().map(|found| found.path).collect::<Vec<_>>();
+
+        assert_eq!(paths(Query::new("Root/a\\/b").unwrap()), ["Root/a\\/b"]);
+        assert_eq!(paths(Query::new("Root/dup").unwrap()), ["Root/dup[0]", "Root/dup[1]"]);
+        assert_eq!(paths(Query::new("Root/dup").unwrap().case_insensitive(true)), ["Root/dup[0]", "Root/Dup", "Root/dup[1]"]);
+        assert_eq!(paths(Query::new("Root/DUP[2]").unwrap().case_insensitive(true)), ["Root/dup[1]"]);
+        assert_eq!(paths(Query::new("/Root/dup[1]/").unwrap()), ["Root/dup[1]"]);
+        assert_eq!(paths(Query::new("*/*/leaf").unwrap()), ["Root/group/leaf"]);
+        assert_eq!(paths(Query::new("Root/*[1]").unwrap()), ["Root/dup[1]"]);
+        assert_eq!(paths(Query::new("Root/dup/deeper").unwrap()), Vec::<String>::new());
+        assert_eq!(paths(Query::new("").unwrap()), [""]);
+
+        assert_eq!(Query::new("Root/*[1]/a\\*").unwrap().to_string(), "Root/*[1]/a\\*");
+        assert!(Query::new("a[x]").is_err());
+        assert!(Query::new("a[1]b").is_err());
+        assert!(Query::new("*a").is_err());
+    }
+}
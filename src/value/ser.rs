@@ -0,0 +1,357 @@
+//! Serialize a Rust data structure into a `Value`
+
+use serde::{ser::{self, Impossible}, Serialize};
+
+use crate::error::{Error, Result};
+use crate::map::Map;
+use crate::ser::MapKeySerializer;
+
+use super::Value;
+
+/// Convert the given data structure into a [`Value`](enum.Value.html)
+///
+/// This works like [`to_string`](fn.to_string.html), except that the top level isn't special:
+/// structs become groups of their fields with no name in front, and newtypes are just what's inside them.
+/// A sequence under a key becomes that key repeated once for each element, the way
+/// [`Serializer::repeated_keys`](struct.Serializer.html#method.repeated_keys) writes it, since a
+/// `Value` can always hold that.
+///
+/// ```
+/// use serde::Serialize;
+/// use vdf_serde::{vdf, Value};
+///
+/// #[derive(Serialize)]
+/// struct UserConfig {
+///     language: String,
+///     betakey: Option<String>,
+/// }
+///
+/// let config = UserConfig { language: "english".to_string(), betakey: Some("beta".to_string()) };
+/// assert_eq!(vdf_serde::to_value(&config)?, vdf! { "language" "english" "betakey" "beta" });
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// The same as `to_string`, without the ones from writing text.
+pub fn to_value<T>(value: &T) -> Result<Value>
+    where
+        T: ?Sized + Serialize,
+{
+    value.serialize(ValueSerializer)?.into_single()
+}
+
+/// Builds a `Value` instead of writing text
+struct ValueSerializer;
+
+/// What serializing something gives, which is one value, or several if it was a sequence
+enum Serialized {
+    /// Anything but a sequence
+    Single(Value),
+    /// The elements of a sequence, to go under the key it was serialized for
+    Repeated(Vec<Value>),
+}
+
+impl Serialized {
+    /// Errors if this is a sequence, since only a key can hold those
+    fn into_single(self) -> Result<Value> {
+        match self {
+            Serialized::Single(value) => Ok(value),
+            Serialized::Repeated(_) => Err(Error::UnsupportedType("seq")),
+        }
+    }
+}
+
+impl From<Value> for Serialized {
+    fn from(value: Value) -> Self {
+        Serialized::Single(value)
+    }
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Serialized;
+
+    type Error = Error;
+
+    type SerializeSeq = SerializeRepeated;
+    type SerializeTuple = SerializeGroup;
+    type SerializeTupleStruct = SerializeGroup;
+    type SerializeTupleVariant = Impossible<Serialized, Error>;
+    type SerializeMap = SerializeGroup;
+    type SerializeStruct = SerializeGroup;
+    type SerializeStructVariant = Impossible<Serialized, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Serialized> {
+        Ok(Value::from(v).into())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Serialized> {
+        Ok(Value::from(v).into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Serialized> {
+        Ok(Value::from(v).into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Serialized> {
+        Ok(Value::from(v).into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Serialized> {
+        Ok(Value::from(v).into())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Serialized> {
+        Ok(Value::from(v).into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Serialized> {
+        Ok(Value::from(v).into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Serialized> {
+        Ok(Value::from(v).into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Serialized> {
+        Ok(Value::from(v).into())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Serialized> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Serialized> {
+        Ok(Value::from(v).into())
+    }
+
+    fn serialize_char(self, v: char) -> Result<Serialized> {
+        Ok(Value::from(v).into())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Serialized> {
+        Ok(Value::from(v).into())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Serialized> {
+        Err(Error::UnsupportedType("byte array"))
+    }
+
+    fn serialize_none(self) -> Result<Serialized> {
+        Err(Error::UnsupportedType("option"))
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Serialized>
+        where
+            T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Serialized> {
+        Ok(Value::from("").into())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Serialized> {
+        Ok(Value::Group(Map::new()).into())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Serialized> {
+        Ok(Value::from(variant).into())
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Serialized>
+        where
+            T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Serialized>
+        where
+            T: ?Sized + Serialize,
+    {
+        Err(Error::UnsupportedType("newtype_variant"))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SerializeRepeated(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Error::UnsupportedType("tuple_variant"))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(SerializeGroup {
+            group: Map::with_capacity(len.unwrap_or(0)),
+            pending_key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Error::UnsupportedType("struct_variant"))
+    }
+
+    fn collect_str<T>(self, value: &T) -> Result<Serialized> where
+        T: ?Sized + std::fmt::Display {
+        Ok(Value::String(value.to_string()).into())
+    }
+}
+
+/// Fills in a group, for maps, structs, and tuples
+struct SerializeGroup {
+    group: Map<String, Value>,
+    pending_key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeGroup {
+    type Ok = Serialized;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+        where
+            T: ?Sized + Serialize,
+    {
+        self.pending_key = Some(key.serialize(MapKeySerializer { bools_as_text: false })?);
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+        where
+            T: ?Sized + Serialize,
+    {
+        let key = self.pending_key.take().unwrap_or_default();
+        match value.serialize(ValueSerializer)? {
+            Serialized::Single(value) => self.group.append(key, value),
+            Serialized::Repeated(values) => {
+                for value in values {
+                    self.group.append(key.clone(), value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<Serialized> {
+        Ok(Value::Group(self.group).into())
+    }
+}
+
+impl ser::SerializeStruct for SerializeGroup {
+    type Ok = Serialized;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+        where
+            T: ?Sized + Serialize,
+    {
+        self.pending_key = Some(key.to_string());
+        ser::SerializeMap::serialize_value(self, value)
+    }
+
+    fn end(self) -> Result<Serialized> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl ser::SerializeTuple for SerializeGroup {
+    type Ok = Serialized;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+        where
+            T: ?Sized + Serialize,
+    {
+        let key = self.group.len().to_string();
+        self.group.append(key, value.serialize(ValueSerializer)?.into_single()?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Serialized> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeGroup {
+    type Ok = Serialized;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+        where
+            T: ?Sized + Serialize,
+    {
+        ser::SerializeTuple::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Serialized> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Gathers the elements of a sequence, which go under a key one at a time
+struct SerializeRepeated(Vec<Value>);
+
+impl ser::SerializeSeq for SerializeRepeated {
+    type Ok = Serialized;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+        where
+            T: ?Sized + Serialize,
+    {
+        self.0.push(value.serialize(ValueSerializer)?.into_single()?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Serialized> {
+        Ok(Serialized::Repeated(self.0))
+    }
+}
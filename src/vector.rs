@@ -0,0 +1,367 @@
+//! Vectors and colors, which VDF stores as space-separated numbers in a single string
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::error::Error;
+
+/// Splits `"1 0 0"`, `"[1 0 0]"` or `"{1 0 0}"` into its numbers, and says which brackets it had
+fn components(text: &str) -> (Option<char>, Vec<&str>) {
+    let text = text.trim();
+    let (bracket, inner) = match (text.chars().next(), text.chars().last()) {
+        (Some('['), Some(']')) => (Some('['), &text[1..text.len() - 1]),
+        (Some('{'), Some('}')) => (Some('{'), &text[1..text.len() - 1]),
+        _ => (None, text),
+    };
+    (bracket, inner.split_whitespace().collect())
+}
+
+fn parse_component<T: FromStr>(text: &str, whole: &str) -> Result<T, Error> {
+    text.parse().map_err(|_| Error::StringParse(format!("invalid number {:?} in {:?}", text, whole)))
+}
+
+/// A point or direction, like an entity's `"origin" "0 0 64"`
+///
+/// This reads from three space-separated numbers, optionally wrapped in `[]` or `{}`
+/// the way materials write them, and is written back the way it was read, which is kept in
+/// [`form`](#structfield.form).
+///
+/// ```
+/// use serde::{Serialize, Deserialize};
+/// use vdf_serde::{Vector3, VectorForm};
+///
+/// #[derive(Serialize, Deserialize, Debug, PartialEq)]
+/// struct Entity {
+///     origin: Vector3,
+/// }
+///
+/// let vdf_data = "\"Entity\"\n{\n\t\"origin\"\t\"0 -16.5 64\"\n}";
+/// let entity = Entity { origin: Vector3::new(0.0, -16.5, 64.0) };
+/// assert_eq!(vdf_serde::from_str::<Entity>(vdf_data)?, entity);
+/// assert_eq!(vdf_serde::to_string(&entity)?, vdf_data);
+/// let vector = "[1 0 0]".parse::<Vector3>()?;
+/// assert_eq!(vector, Vector3::new(1.0, 0.0, 0.0).with_form(VectorForm::Brackets));
+/// assert_eq!(vector.to_string(), "[1 0 0]");
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vector3 {
+    /// The first component
+    pub x: f32,
+    /// The second component
+    pub y: f32,
+    /// The third component
+    pub z: f32,
+    /// What the numbers are wrapped in
+    pub form: VectorForm,
+}
+
+/// What a [`Vector3`](struct.Vector3.html)'s numbers are wrapped in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum VectorForm {
+    /// `"1 0 0"`
+    #[default]
+    Plain,
+    /// `"{1 0 0}"`
+    Braces,
+    /// `"[1 0 0]"`
+    Brackets,
+}
+
+impl Vector3 {
+    /// Creates a `Vector3` from its components, to be written as plain numbers
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Vector3 { x, y, z, form: VectorForm::Plain }
+    }
+
+    /// Sets what the numbers are wrapped in
+    pub fn with_form(mut self, form: VectorForm) -> Self {
+        self.form = form;
+        self
+    }
+}
+
+impl From<[f32; 3]> for Vector3 {
+    fn from([x, y, z]: [f32; 3]) -> Self {
+        Vector3::new(x, y, z)
+    }
+}
+
+impl From<Vector3> for [f32; 3] {
+    fn from(vector: Vector3) -> Self {
+        [vector.x, vector.y, vector.z]
+    }
+}
+
+impl FromStr for Vector3 {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Error> {
+        let (bracket, parts) = components(text);
+        let form = match bracket {
+            Some('[') => VectorForm::Brackets,
+            Some('{') => VectorForm::Braces,
+            _ => VectorForm::Plain,
+        };
+        match parts.as_slice() {
+            [x, y, z] => Ok(Vector3 {
+                x: parse_component(x, text)?,
+                y: parse_component(y, text)?,
+                z: parse_component(z, text)?,
+                form,
+            }),
+            _ => Err(Error::StringParse(format!("expected three numbers, got {:?}", text))),
+        }
+    }
+}
+
+impl Display for Vector3 {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self.form {
+            VectorForm::Plain => write!(formatter, "{} {} {}", self.x, self.y, self.z),
+            VectorForm::Braces => write!(formatter, "{{{} {} {}}}", self.x, self.y, self.z),
+            VectorForm::Brackets => write!(formatter, "[{} {} {}]", self.x, self.y, self.z),
+        }
+    }
+}
+
+/// A color, like an entity's `"rendercolor" "255 128 0"` or a material's `"$color" "[1 0.5 0]"`
+///
+/// This reads from three or four space-separated numbers. Plain numbers and numbers wrapped in
+/// `{}` go from 0 to 255, and numbers wrapped in `[]` go from 0 to 1. If there's no fourth
+/// number, alpha is 255, and if alpha is 255 it's left out when writing, unless it was read.
+/// The fourth of plain or `{}` numbers can go past 255, since lights like `"_light" "255 255 255 600"`
+/// keep their brightness there.
+///
+/// A color is written back the way it was read, which is kept in [`form`](#structfield.form).
+/// Numbers from 0 to 1 are written back exactly as they were read unless their channel changes.
+///
+/// Two colors are equal, and hash the same, when their channels and `form` are. That ignores the exact
+/// numbers from 0 to 1 they were read as and whether alpha was read, so equal colors can still be
+/// written differently, like `[1 0.33 0]` and `[1 0.329 0]`, or `255 0 0` and `255 0 0 255`.
+///
+/// ```
+/// use vdf_serde::{Color, ColorForm};
+///
+/// assert_eq!("{255 128 0}".parse::<Color>()?, Color::new(255, 128, 0, 255).with_form(ColorForm::Braces));
+/// assert_eq!("[1 0.5 0 1]".parse::<Color>()?.to_string(), "[1 0.5 0 1]");
+/// assert_eq!("255 255 255 600".parse::<Color>()?.a, 600);
+/// assert_eq!("[1 0.33 0]".parse::<Color>()?.to_string(), "[1 0.33 0]");
+/// assert_eq!(Color::new(255, 255, 255, 200).to_string(), "255 255 255 200");
+/// assert_eq!(Color::new(255, 0, 0, 255).with_form(ColorForm::Brackets).to_string(), "[1 0 0]");
+/// assert_eq!("255 0 0 255".parse::<Color>()?, "255 0 0".parse::<Color>()?);
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Color {
+    /// Red
+    pub r: u8,
+    /// Green
+    pub g: u8,
+    /// Blue
+    pub b: u8,
+    /// Alpha, or brightness for lights
+    pub a: u16,
+    /// How the numbers are written
+    pub form: ColorForm,
+    /// The numbers from 0 to 1 as they were read, if they were
+    unit: Option<[f32; 4]>,
+    /// Whether alpha was read, so it's written back even if it's 255
+    alpha_read: bool,
+}
+
+/// How a [`Color`](struct.Color.html)'s numbers are written
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorForm {
+    /// `"255 128 0"`, from 0 to 255
+    #[default]
+    Plain,
+    /// `"{255 128 0}"`, from 0 to 255
+    Braces,
+    /// `"[1 0.5 0]"`, from 0 to 1
+    Brackets,
+}
+
+impl Color {
+    /// Creates a `Color` from its components, to be written as plain numbers from 0 to 255
+    pub fn new(r: u8, g: u8, b: u8, a: u16) -> Self {
+        Color { r, g, b, a, form: ColorForm::Plain, unit: None, alpha_read: false }
+    }
+
+    /// Sets how the color is written
+    pub fn with_form(mut self, form: ColorForm) -> Self {
+        self.form = form;
+        self
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Color::new(255, 255, 255, 255)
+    }
+}
+
+impl PartialEq for Color {
+    fn eq(&self, other: &Self) -> bool {
+        (self.r, self.g, self.b, self.a, self.form) == (other.r, other.g, other.b, other.a, other.form)
+    }
+}
+
+impl Eq for Color {}
+
+impl std::hash::Hash for Color {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        (self.r, self.g, self.b, self.a, self.form).hash(state);
+    }
+}
+
+/// Turns a number from 0 to 1 into a channel
+fn unit_to_channel(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Writes a channel as the shortest number from 0 to 1 that reads back as the same channel
+///
+/// Anything past 255 is written as 1.
+fn channel_to_unit(channel: u16) -> String {
+    let channel = channel.min(255) as u8;
+    (0..=3)
+        .map(|digits| format!("{:.*}", digits, f32::from(channel) / 255.0))
+        .find(|text| text.parse().map(unit_to_channel) == Ok(channel))
+        .map(|text| if text.contains('.') { text.trim_end_matches('0').trim_end_matches('.').to_string() } else { text })
+        .expect("three digits tell every channel apart")
+}
+
+impl FromStr for Color {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Error> {
+        let (bracket, parts) = components(text);
+        if parts.len() != 3 && parts.len() != 4 {
+            return Err(Error::StringParse(format!("expected three or four numbers, got {:?}", text)));
+        }
+        let alpha_read = parts.len() == 4;
+        let mut rgba = [255u16; 4];
+        let mut unit = [1.0f32; 4];
+        for (i, ((channel, unit), part)) in rgba.iter_mut().zip(unit.iter_mut()).zip(parts).enumerate() {
+            *channel = if bracket == Some('[') {
+                *unit = parse_component(part, text)?;
+                unit_to_channel(*unit).into()
+            } else if i < 3 {
+                parse_component::<u8>(part, text)?.into()
+            } else {
+                parse_component(part, text)?
+            };
+        }
+        let [r, g, b, a] = rgba;
+        let (form, unit) = match bracket {
+            Some('[') => (ColorForm::Brackets, Some(unit)),
+            Some('{') => (ColorForm::Braces, None),
+            _ => (ColorForm::Plain, None),
+        };
+        // only alpha can be read past 255
+        let [r, g, b] = [r, g, b].map(|channel| channel as u8);
+        Ok(Color { r, g, b, a, form, unit, alpha_read })
+    }
+}
+
+impl Display for Color {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let channels = [self.r.into(), self.g.into(), self.b.into(), self.a];
+        let count = if self.a == 255 && !self.alpha_read { 3 } else { 4 };
+        let numbers: Vec<String> = match self.form {
+            ColorForm::Brackets => channels.iter().enumerate().take(count).map(|(i, &channel)| {
+                match self.unit {
+                    Some(unit) if u16::from(unit_to_channel(unit[i])) == channel => unit[i].to_string(),
+                    _ => channel_to_unit(channel),
+                }
+            }).collect(),
+            _ => channels.iter().take(count).map(|channel| channel.to_string()).collect(),
+        };
+        match self.form {
+            ColorForm::Plain => write!(formatter, "{}", numbers.join(" ")),
+            ColorForm::Braces => write!(formatter, "{{{}}}", numbers.join(" ")),
+            ColorForm::Brackets => write!(formatter, "[{}]", numbers.join(" ")),
+        }
+    }
+}
+
+/// Reads anything that's `FromStr` out of a string value
+pub(crate) struct FromStrVisitor<T>(pub(crate) &'static str, pub(crate) std::marker::PhantomData<T>);
+
+impl<'de, T: FromStr<Err = Error>> Visitor<'de> for FromStrVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(self.0)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
+impl Serialize for Vector3 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Vector3 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(FromStrVisitor("three space-separated numbers", std::marker::PhantomData))
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(FromStrVisitor("three or four space-separated numbers", std::marker::PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(" { 1 2.5   -3 } ".parse::<Vector3>(), Ok(Vector3::new(1.0, 2.5, -3.0).with_form(VectorForm::Braces)));
+        for text in &["1 2.5 -3", "{1 2.5 -3}", "[1 2.5 -3]"] {
+            assert_eq!(text.parse::<Vector3>().unwrap().to_string(), *text);
+        }
+        assert!("1 2".parse::<Vector3>().is_err());
+        assert!("1 2 x".parse::<Vector3>().is_err());
+        assert!("[1 2 3".parse::<Vector3>().is_err());
+
+        assert_eq!("10 20 30".parse::<Color>(), Ok(Color::new(10, 20, 30, 255)));
+        assert_eq!("[0 0.2 2]".parse::<Color>(), Ok(Color::new(0, 51, 255, 255).with_form(ColorForm::Brackets)));
+        assert!("256 0 0".parse::<Color>().is_err());
+        assert_eq!("255 200 100 600".parse::<Color>(), Ok(Color::new(255, 200, 100, 600)));
+        assert!("[1 1 1 600]".parse::<Color>().is_ok_and(|color| color.a == 255));
+        assert_eq!(Color::new(1, 2, 3, 255).to_string(), "1 2 3");
+    }
+
+    #[test]
+    fn test_color_forms() {
+        for text in &["255 128 0", "{255 128 0}", "{1 2 3 4}", "[1 0.5 0]", "[0.123 0.7 0.35 0.5]", "[0 0 0]", "[1 0.5 0 1]", "255 255 255 255", "255 255 255 600"] {
+            assert_eq!(text.parse::<Color>().unwrap().to_string(), *text);
+        }
+        // a channel that changed is written from its new value
+        let mut color = "[0.123 0.5 0]".parse::<Color>().unwrap();
+        color.r = 64;
+        assert_eq!(color.to_string(), "[0.25 0.5 0]");
+        assert_eq!(color.with_form(ColorForm::Braces).to_string(), "{64 128 0}");
+        assert_eq!(Color::new(51, 128, 255, 255).with_form(ColorForm::Brackets).to_string(), "[0.2 0.5 1]");
+        assert_eq!(Color::new(255, 0, 0, 600).with_form(ColorForm::Brackets).to_string(), "[1 0 0 1]");
+        assert_eq!((0..=255).map(channel_to_unit).map(|text| text.parse().map(unit_to_channel)).collect::<Vec<_>>(), (0..=255).map(Ok).collect::<Vec<_>>());
+    }
+}
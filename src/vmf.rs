@@ -0,0 +1,708 @@
+//! Maps, from the `.vmf` files that Hammer saves them in
+//!
+//! A `.vmf` file is a list of top-level groups with no name or braces around them, where the
+//! world's brushes are repeated `solid` groups, each of their faces is a repeated `side` group,
+//! and every entity is a repeated `entity` group. These are read with
+//! [`DuplicateKeys::Collect`](../enum.DuplicateKeys.html#variant.Collect) and
+//! [`Deserializer::bare_top_level`](../struct.Deserializer.html#method.bare_top_level), which is what
+//! [`from_str`](fn.from_str.html) sets up, and written back out as one group for each element.
+//!
+//! Anything that isn't modelled, like an entity's `connections` and `editor`, the world's `hidden`
+//! brushes, or top-level groups that other editors add, is kept as a [`Value`](../enum.Value.html).
+//!
+//! ```
+//! use vdf_serde::vmf::{self, Vmf};
+//!
+//! let map: Vmf = vmf::from_str(r#"
+//! world
+//! {
+//!     "id" "1"
+//!     "classname" "worldspawn"
+//!     "skyname" "sky_day01_01"
+//!     solid
+//!     {
+//!         "id" "2"
+//!         side { "id" "1" "plane" "(0 0 64) (64 0 64) (64 -64 64)" "material" "DEV/DEV_MEASUREGENERIC01B"
+//!             "uaxis" "[1 0 0 0] 0.25" "vaxis" "[0 -1 0 0] 0.25" "rotation" "0" "lightmapscale" "16" "smoothing_groups" "0" }
+//!     }
+//! }
+//! entity { "id" "3" "classname" "info_player_start" "origin" "32 -32 64" }
+//! entity { "id" "4" "classname" "light" "origin" "0 0 128" }
+//! "#)?;
+//! assert_eq!(map.world.property("skyname"), Some("sky_day01_01"));
+//! assert_eq!(map.world.solids[0].sides[0].plane.0[1].x, 64.0);
+//! assert_eq!(map.entities.iter().map(|entity| entity.classname.as_str()).collect::<Vec<_>>(), ["info_player_start", "light"]);
+//!
+//! let written = vmf::to_string(&map)?;
+//! assert!(written.starts_with("\"world\"\n{\n\t\"id\"\t\"1\"\n\t\"classname\"\t\"worldspawn\"\n"));
+//! assert_eq!(vmf::from_str::<Vmf>(&written)?, map);
+//! # Ok::<(), vdf_serde::Error>(())
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::de::DuplicateKeys;
+use crate::error::{Error, Result};
+use crate::map::Map;
+use crate::value::Value;
+use crate::vector::{FromStrVisitor, Vector3};
+
+/// A whole map
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Vmf {
+    /// Which version of Hammer saved the map, and how many times it's been saved
+    pub version_info: Option<Value>,
+    /// The visgroups that things in the map can be put in to hide them in Hammer
+    pub visgroups: Option<Value>,
+    /// Hammer's grid and snapping settings
+    pub view_settings: Option<Value>,
+    /// The world, which has the map's brushes and its own settings like the skybox
+    pub world: World,
+    /// The entities, in order
+    pub entities: Vec<Entity>,
+    /// The entities that are hidden in Hammer, which it writes in a `hidden` group each
+    pub hidden: Vec<Entity>,
+    /// The cameras in Hammer's 3D view
+    pub cameras: Option<Value>,
+    /// The cordon, from versions of Hammer that only had one
+    pub cordon: Option<Value>,
+    /// The cordons, from versions of Hammer that can have more than one
+    pub cordons: Option<Value>,
+    /// Any other top-level groups, like Hammer++'s `palette_plus`, in order, along with any
+    /// repeats of the ones above
+    pub other: Map<String, Value>,
+}
+
+/// The world, which is the `worldspawn` entity that every brush that isn't part of another entity belongs to
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Body", into = "Body")]
+pub struct World {
+    /// Its ID, which is always 1 in maps Hammer saved
+    pub id: u32,
+    /// Always `"worldspawn"`
+    pub classname: String,
+    /// Its keyvalues, like `"skyname"` and `"mapversion"`, in order
+    pub properties: Map<String, String>,
+    /// Its brushes
+    pub solids: Vec<Solid>,
+    /// Any other groups in it, like `hidden` brushes and visgroup `group`s, in order
+    pub groups: Map<String, Value>,
+}
+
+/// An entity, either a point entity or a brush entity with solids of its own
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Body", into = "Body")]
+pub struct Entity {
+    /// Its ID, which is unique among entities and the world
+    pub id: u32,
+    /// What kind of entity it is, like `"info_player_start"`
+    pub classname: String,
+    /// Its keyvalues, like `"origin"` and `"targetname"`, in order
+    pub properties: Map<String, String>,
+    /// Its brushes, if it's a brush entity
+    pub solids: Vec<Solid>,
+    /// Any other groups in it, like `connections` and `editor`, in order
+    pub groups: Map<String, Value>,
+}
+
+/// A brush, which is a convex solid made of the space behind all of its sides
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Solid {
+    /// Its ID, which is unique among solids
+    pub id: u32,
+    /// Its faces
+    #[serde(rename = "side")]
+    pub sides: Vec<Side>,
+    /// Hammer's settings for it, like its color and visgroups
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editor: Option<Value>,
+    /// Anything else in it, in order
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// One face of a brush
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Side {
+    /// Its ID, which is unique among sides
+    pub id: u32,
+    /// The plane the face is on
+    pub plane: Plane,
+    /// The material it's drawn with, like `"TOOLS/TOOLSNODRAW"`
+    pub material: String,
+    /// How the material's horizontal axis is projected onto it, like `"[1 0 0 0] 0.25"`
+    pub uaxis: String,
+    /// How the material's vertical axis is projected onto it, like `"[0 -1 0 0] 0.25"`
+    pub vaxis: String,
+    /// How far the material is rotated, in degrees
+    pub rotation: f64,
+    /// How many units each luxel of its lightmap covers
+    pub lightmapscale: u32,
+    /// Which of the 32 smoothing groups it's in, as bits
+    pub smoothing_groups: u32,
+    /// The displacement on it, if it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dispinfo: Option<Value>,
+    /// Anything else in it, in order
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+/// A plane, from three points on it, like `"(0 0 64) (64 0 64) (64 -64 64)"`
+///
+/// The points go clockwise when looking at the front of the face.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Plane(pub [Vector3; 3]);
+
+impl World {
+    /// Returns a keyvalue, if it's there
+    pub fn property(&self, key: &str) -> Option<&str> {
+        property(&self.properties, key)
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World {
+            id: 1,
+            classname: "worldspawn".to_string(),
+            properties: Map::new(),
+            solids: Vec::new(),
+            groups: Map::new(),
+        }
+    }
+}
+
+impl Entity {
+    /// Creates an entity with no keyvalues yet
+    pub fn new(id: u32, classname: impl Into<String>) -> Self {
+        Entity {
+            id,
+            classname: classname.into(),
+            ..Entity::default()
+        }
+    }
+
+    /// Returns a keyvalue, if it's there
+    pub fn property(&self, key: &str) -> Option<&str> {
+        property(&self.properties, key)
+    }
+}
+
+fn property<'a>(properties: &'a Map<String, String>, key: &str) -> Option<&'a str> {
+    properties.iter().find(|(other, _)| *other == key).map(|(_, value)| value.as_str())
+}
+
+impl FromStr for Plane {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let invalid = || Error::StringParse(format!("expected three points in parentheses, got {:?}", text));
+        let mut points = [Vector3::default(); 3];
+        let mut rest = text.trim();
+        for point in &mut points {
+            let end = rest.find(')').ok_or_else(invalid)?;
+            *point = rest[..end].strip_prefix('(').ok_or_else(invalid)?.parse()?;
+            rest = rest[end + 1..].trim_start();
+        }
+        if !rest.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Plane(points))
+    }
+}
+
+impl fmt::Display for Plane {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let [a, b, c] = &self.0;
+        write!(formatter, "({}) ({}) ({})", a, b, c)
+    }
+}
+
+impl Serialize for Plane {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Plane {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_str(FromStrVisitor("three points in parentheses", std::marker::PhantomData))
+    }
+}
+
+impl Serialize for Vmf {
+    /// Writes the groups in the order Hammer does, followed by any others
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        if let Some(version_info) = &self.version_info {
+            map.serialize_entry("versioninfo", version_info)?;
+        }
+        if let Some(visgroups) = &self.visgroups {
+            map.serialize_entry("visgroups", visgroups)?;
+        }
+        if let Some(view_settings) = &self.view_settings {
+            map.serialize_entry("viewsettings", view_settings)?;
+        }
+        map.serialize_entry("world", &self.world)?;
+        if !self.entities.is_empty() {
+            map.serialize_entry("entity", &self.entities)?;
+        }
+        if !self.hidden.is_empty() {
+            map.serialize_entry("hidden", &self.hidden.iter().map(HiddenEntity).collect::<Vec<_>>())?;
+        }
+        for (key, group) in [("cameras", &self.cameras), ("cordon", &self.cordon), ("cordons", &self.cordons)] {
+            if let Some(group) = group {
+                map.serialize_entry(key, group)?;
+            }
+        }
+        for (key, group) in self.other.iter() {
+            map.serialize_entry(key, group)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Vmf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_map(VmfVisitor)
+    }
+}
+
+struct VmfVisitor;
+
+impl<'de> Visitor<'de> for VmfVisitor {
+    type Value = Vmf;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("the top-level groups of a map")
+    }
+
+    /// Every key but `world` can repeat, so their values are all read as sequences
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Vmf, A::Error> {
+        let mut world = None;
+        let mut vmf = Vmf::default();
+        while let Some(key) = map.next_key::<String>()? {
+            let field = match key.as_str() {
+                "world" => {
+                    world = Some(map.next_value()?);
+                    continue;
+                }
+                "entity" => {
+                    vmf.entities.extend(map.next_value::<Vec<Entity>>()?);
+                    continue;
+                }
+                "hidden" => {
+                    vmf.hidden.extend(map.next_value::<Vec<Hidden>>()?.into_iter().flat_map(|hidden| hidden.entities));
+                    continue;
+                }
+                "versioninfo" => Some(&mut vmf.version_info),
+                "visgroups" => Some(&mut vmf.visgroups),
+                "viewsettings" => Some(&mut vmf.view_settings),
+                "cameras" => Some(&mut vmf.cameras),
+                "cordon" => Some(&mut vmf.cordon),
+                "cordons" => Some(&mut vmf.cordons),
+                _ => None,
+            };
+            let mut values = map.next_value::<Vec<Value>>()?.into_iter();
+            if let Some(field) = field.filter(|field| field.is_none()) {
+                *field = values.next();
+            }
+            vmf.other.extend(values.map(|value| (key.clone(), value)));
+        }
+        vmf.world = world.ok_or_else(|| de::Error::missing_field("world"))?;
+        Ok(vmf)
+    }
+}
+
+/// A `hidden` group at the top level, which is how Hammer writes entities that are hidden
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Hidden {
+    #[serde(rename = "entity", default)]
+    entities: Vec<Entity>,
+}
+
+/// A hidden entity, written in a `hidden` group of its own
+struct HiddenEntity<'a>(&'a Entity);
+
+impl Serialize for HiddenEntity<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry("entity", self.0)?;
+        map.end()
+    }
+}
+
+/// What the world and entities both look like in the file
+#[derive(Clone, Default)]
+struct Body {
+    id: u32,
+    classname: String,
+    properties: Map<String, String>,
+    solids: Vec<Solid>,
+    groups: Map<String, Value>,
+}
+
+impl From<Body> for World {
+    fn from(body: Body) -> Self {
+        World { id: body.id, classname: body.classname, properties: body.properties, solids: body.solids, groups: body.groups }
+    }
+}
+
+impl From<World> for Body {
+    fn from(world: World) -> Self {
+        Body { id: world.id, classname: world.classname, properties: world.properties, solids: world.solids, groups: world.groups }
+    }
+}
+
+impl From<Body> for Entity {
+    fn from(body: Body) -> Self {
+        Entity { id: body.id, classname: body.classname, properties: body.properties, solids: body.solids, groups: body.groups }
+    }
+}
+
+impl From<Entity> for Body {
+    fn from(entity: Entity) -> Self {
+        Body { id: entity.id, classname: entity.classname, properties: entity.properties, solids: entity.solids, groups: entity.groups }
+    }
+}
+
+impl Serialize for Body {
+    /// Writes the keyvalues, then the solids, then the other groups, which is the order Hammer uses
+    /// except that it puts `connections` before the solids
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("id", &self.id)?;
+        map.serialize_entry("classname", &self.classname)?;
+        for (key, value) in self.properties.iter() {
+            map.serialize_entry(key, value)?;
+        }
+        if !self.solids.is_empty() {
+            map.serialize_entry("solid", &self.solids)?;
+        }
+        for (key, group) in self.groups.iter() {
+            map.serialize_entry(key, group)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Body {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_map(BodyVisitor)
+    }
+}
+
+struct BodyVisitor;
+
+impl<'de> Visitor<'de> for BodyVisitor {
+    type Value = Body;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an entity group")
+    }
+
+    /// Every key but `id` and `classname` can repeat, so their values are all read as sequences
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Body, A::Error> {
+        let (mut id, mut classname) = (None, None);
+        let mut body = Body::default();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "id" => id = Some(map.next_value()?),
+                "classname" => classname = Some(map.next_value()?),
+                // props have a "solid" keyvalue too, for what kind of collisions they have
+                "solid" => {
+                    for solid in map.next_value::<Vec<SolidOrProperty>>()? {
+                        match solid {
+                            SolidOrProperty::Solid(solid) => body.solids.push(solid),
+                            SolidOrProperty::Property(text) => body.properties.append(key.clone(), text),
+                        }
+                    }
+                }
+                _ => {
+                    for value in map.next_value::<Vec<Value>>()? {
+                        match value {
+                            Value::String(text) => body.properties.append(key.clone(), text),
+                            group => body.groups.append(key.clone(), group),
+                        }
+                    }
+                }
+            }
+        }
+        body.id = id.ok_or_else(|| de::Error::missing_field("id"))?;
+        body.classname = classname.ok_or_else(|| de::Error::missing_field("classname"))?;
+        Ok(body)
+    }
+}
+
+impl<'de> Deserialize<'de> for Solid {
+    /// Reads a solid as any value, since its sides repeat and that makes it a sequence of one-entry maps
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(SolidVisitor)
+    }
+}
+
+struct SolidVisitor;
+
+impl<'de> Visitor<'de> for SolidVisitor {
+    type Value = Solid;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a solid group")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Solid, A::Error> {
+        let mut fields = SolidFields::default();
+        fields.read(&mut map)?;
+        fields.finish()
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Solid, A::Error> {
+        let mut fields = SolidFields::default();
+        while seq.next_element_seed(&mut fields)?.is_some() {}
+        fields.finish()
+    }
+}
+
+/// A solid's fields as they're found, which might be one entry at a time
+#[derive(Default)]
+struct SolidFields {
+    id: Option<u32>,
+    sides: Vec<Side>,
+    editor: Option<Value>,
+    other: Map<String, Value>,
+}
+
+impl SolidFields {
+    fn read<'de, A: MapAccess<'de>>(&mut self, map: &mut A) -> std::result::Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "id" => self.id = Some(map.next_value()?),
+                "side" => self.sides.push(map.next_value()?),
+                "editor" => self.editor = Some(map.next_value()?),
+                _ => self.other.append(key, map.next_value()?),
+            }
+        }
+        Ok(())
+    }
+
+    fn finish<E: de::Error>(self) -> std::result::Result<Solid, E> {
+        Ok(Solid {
+            id: self.id.ok_or_else(|| E::missing_field("id"))?,
+            sides: self.sides,
+            editor: self.editor,
+            other: self.other,
+        })
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for &mut SolidFields {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for &mut SolidFields {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an entry of a solid group")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<(), A::Error> {
+        self.read(&mut map)
+    }
+}
+
+impl<'de> Deserialize<'de> for Side {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_map(SideVisitor)
+    }
+}
+
+struct SideVisitor;
+
+impl<'de> Visitor<'de> for SideVisitor {
+    type Value = Side;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a side group")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Side, A::Error> {
+        let (mut id, mut plane, mut material, mut uaxis, mut vaxis) = (None, None, None, None, None);
+        let (mut rotation, mut lightmapscale, mut smoothing_groups, mut dispinfo) = (None, None, None, None);
+        let mut other = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "id" => id = Some(map.next_value()?),
+                "plane" => plane = Some(map.next_value()?),
+                "material" => material = Some(map.next_value()?),
+                "uaxis" => uaxis = Some(map.next_value()?),
+                "vaxis" => vaxis = Some(map.next_value()?),
+                "rotation" => rotation = Some(map.next_value()?),
+                "lightmapscale" => lightmapscale = Some(map.next_value()?),
+                "smoothing_groups" => smoothing_groups = Some(map.next_value()?),
+                "dispinfo" => dispinfo = Some(map.next_value()?),
+                // anything else might repeat, so it's read as a sequence like the keys of a body
+                _ => other.extend(map.next_value::<Vec<Value>>()?.into_iter().map(|value| (key.clone(), value))),
+            }
+        }
+        Ok(Side {
+            id: id.ok_or_else(|| de::Error::missing_field("id"))?,
+            plane: plane.ok_or_else(|| de::Error::missing_field("plane"))?,
+            material: material.ok_or_else(|| de::Error::missing_field("material"))?,
+            uaxis: uaxis.ok_or_else(|| de::Error::missing_field("uaxis"))?,
+            vaxis: vaxis.ok_or_else(|| de::Error::missing_field("vaxis"))?,
+            rotation: rotation.ok_or_else(|| de::Error::missing_field("rotation"))?,
+            lightmapscale: lightmapscale.ok_or_else(|| de::Error::missing_field("lightmapscale"))?,
+            smoothing_groups: smoothing_groups.ok_or_else(|| de::Error::missing_field("smoothing_groups"))?,
+            dispinfo,
+            other,
+        })
+    }
+}
+
+/// A value under the key `"solid"`, which is a brush in a group or a keyvalue in a string
+enum SolidOrProperty {
+    Solid(Solid),
+    Property(String),
+}
+
+impl<'de> Deserialize<'de> for SolidOrProperty {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(SolidOrPropertyVisitor)
+    }
+}
+
+struct SolidOrPropertyVisitor;
+
+impl<'de> Visitor<'de> for SolidOrPropertyVisitor {
+    type Value = SolidOrProperty;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a solid group or a string")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<SolidOrProperty, E> {
+        Ok(SolidOrProperty::Property(v.to_string()))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> std::result::Result<SolidOrProperty, A::Error> {
+        SolidVisitor.visit_map(map).map(SolidOrProperty::Solid)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> std::result::Result<SolidOrProperty, A::Error> {
+        SolidVisitor.visit_seq(seq).map(SolidOrProperty::Solid)
+    }
+}
+
+impl FromStr for Vmf {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        from_str(s)
+    }
+}
+
+/// Deserialize a `.vmf` file, or anything else laid out like one
+///
+/// # Errors
+///
+/// The same as [`vdf_serde::from_str`](../fn.from_str.html).
+pub fn from_str<'a, T>(s: &'a str) -> Result<T> where T: Deserialize<'a> {
+    let mut deserializer = crate::de::Deserializer::from_str(s).duplicate_keys(DuplicateKeys::Collect).bare_top_level(true);
+    let t = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(t)
+}
+
+/// Serialize a `.vmf` file, or anything else laid out like one, with its top-level groups on their own
+///
+/// # Errors
+///
+/// The same as [`vdf_serde::to_string`](../fn.to_string.html).
+pub fn to_string<T>(value: &T) -> Result<String> where T: Serialize {
+    let mut serializer = crate::ser::Serializer::new().bare_top_level(true).repeated_keys(true);
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vmf() {
+        let text = concat!(
+            "versioninfo\n{\n\t\"editorversion\" \"400\"\n\t\"mapversion\" \"3\"\n}\n",
+            "world\n{\n",
+            "\t\"id\" \"1\"\n\t\"mapversion\" \"3\"\n\t\"classname\" \"worldspawn\"\n",
+            "\tsolid\n\t{\n\t\t\"id\" \"2\"\n",
+            "\t\tside\n\t\t{\n\t\t\t\"id\" \"1\"\n\t\t\t\"plane\" \"(-64 -64 0) (-64 64 0) (64 64 0)\"\n",
+            "\t\t\t\"material\" \"TOOLS/TOOLSNODRAW\"\n\t\t\t\"uaxis\" \"[1 0 0 0] 0.25\"\n\t\t\t\"vaxis\" \"[0 -1 0 0] 0.25\"\n",
+            "\t\t\t\"rotation\" \"0\"\n\t\t\t\"lightmapscale\" \"16\"\n\t\t\t\"smoothing_groups\" \"0\"\n",
+            "\t\t\tvertices_plus\n\t\t\t{\n\t\t\t\t\"v\" \"-64 -64 0\"\n\t\t\t\t\"v\" \"-64 64 0\"\n\t\t\t\t\"v\" \"64 64 0\"\n\t\t\t}\n\t\t}\n",
+            "\t\tside\n\t\t{\n\t\t\t\"id\" \"2\"\n\t\t\t\"plane\" \"(-64 64 -16) (-64 -64 -16) (64 -64 -16)\"\n",
+            "\t\t\t\"material\" \"DEV/DEV_MEASUREGENERIC01B\"\n\t\t\t\"uaxis\" \"[1 0 0 0] 0.25\"\n\t\t\t\"vaxis\" \"[0 -1 0 0] 0.25\"\n",
+            "\t\t\t\"rotation\" \"90\"\n\t\t\t\"lightmapscale\" \"16\"\n\t\t\t\"smoothing_groups\" \"0\"\n\t\t}\n",
+            "\t\teditor\n\t\t{\n\t\t\t\"color\" \"0 180 255\"\n\t\t\t\"visgroupshown\" \"1\"\n\t\t}\n",
+            "\t\t\"exclude_from_export\" \"1\"\n",
+            "\t}\n",
+            "\thidden\n\t{\n\t\tsolid\n\t\t{\n\t\t\t\"id\" \"5\"\n\t\t}\n\t}\n",
+            "\thidden\n\t{\n\t\tsolid\n\t\t{\n\t\t\t\"id\" \"6\"\n\t\t}\n\t}\n",
+            "}\n",
+            "entity\n{\n\t\"id\" \"7\"\n\t\"classname\" \"prop_dynamic\"\n\t\"solid\" \"6\"\n",
+            "\tconnections\n\t{\n\t\t\"OnUser1\" \"door,Open,,0,-1\"\n\t\t\"OnUser1\" \"door,Close,,5,-1\"\n\t}\n}\n",
+            "entity\n{\n\t\"id\" \"8\"\n\t\"classname\" \"info_player_start\"\n\t\"origin\" \"0 0 8\"\n}\n",
+            "hidden\n{\n\tentity\n\t{\n\t\t\"id\" \"9\"\n\t\t\"classname\" \"light\"\n\t}\n}\n",
+            "cameras\n{\n\t\"activecamera\" \"-1\"\n}\n",
+            "palette_plus\n{\n\t\"color0\" \"255 255 255\"\n}\n"
+        );
+        let map: Vmf = text.parse().unwrap();
+        assert_eq!(map.world.property("mapversion"), Some("3"));
+        let solid = &map.world.solids[0];
+        assert_eq!(solid.sides.iter().map(|side| side.id).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(solid.sides[1].plane, Plane([Vector3::new(-64.0, 64.0, -16.0), Vector3::new(-64.0, -64.0, -16.0), Vector3::new(64.0, -64.0, -16.0)]));
+        assert_eq!(solid.sides[1].rotation, 90.0);
+        assert!(solid.editor.is_some());
+        assert_eq!(solid.other.get("exclude_from_export").and_then(Value::as_str), Some("1"));
+        let vertices = solid.sides[0].other.get("vertices_plus").and_then(Value::as_group).unwrap();
+        assert_eq!(vertices.values().filter_map(Value::as_str).collect::<Vec<_>>(), ["-64 -64 0", "-64 64 0", "64 64 0"]);
+        assert!(solid.sides[1].other.is_empty());
+        assert_eq!(map.world.groups.keys().collect::<Vec<_>>(), ["hidden", "hidden"]);
+
+        let prop = &map.entities[0];
+        assert_eq!(prop.property("solid"), Some("6"));
+        assert!(prop.solids.is_empty());
+        assert_eq!(prop.groups.iter().next().unwrap().1.to_string(), "\"OnUser1\"\t\"door,Open,,0,-1\"\n\"OnUser1\"\t\"door,Close,,5,-1\"");
+        assert_eq!(map.entities[1].property("origin"), Some("0 0 8"));
+        assert_eq!(map.hidden.iter().map(|entity| entity.id).collect::<Vec<_>>(), [9]);
+        assert_eq!(map.other.keys().collect::<Vec<_>>(), ["palette_plus"]);
+        assert_eq!(map.cameras.as_ref().and_then(|cameras| cameras.get("activecamera")).and_then(Value::as_str), Some("-1"));
+
+        let written = to_string(&map).unwrap();
+        assert!(written.starts_with("\"versioninfo\"\n{\n\t\"editorversion\"\t\"400\"\n\t\"mapversion\"\t\"3\"\n}\n\"world\"\n{\n"));
+        assert!(written.contains("\t\"solid\"\n\t{\n\t\t\"id\"\t\"2\"\n\t\t\"side\"\n\t\t{\n\t\t\t\"id\"\t\"1\"\n\t\t\t\"plane\"\t\"(-64 -64 0) (-64 64 0) (64 64 0)\"\n"));
+        assert!(written.contains("\"entity\"\n{\n\t\"id\"\t\"7\"\n\t\"classname\"\t\"prop_dynamic\"\n\t\"solid\"\t\"6\"\n\t\"connections\"\n"));
+        assert!(written.contains("\t\t\t\"smoothing_groups\"\t\"0\"\n\t\t\t\"vertices_plus\"\n\t\t\t{\n\t\t\t\t\"v\"\t\"-64 -64 0\"\n"));
+        assert!(written.contains("\n}\n\"hidden\"\n{\n\t\"entity\"\n\t{\n\t\t\"id\"\t\"9\"\n"));
+        assert!(written.ends_with("\"cameras\"\n{\n\t\"activecamera\"\t\"-1\"\n}\n\"palette_plus\"\n{\n\t\"color0\"\t\"255 255 255\"\n}"));
+        assert_eq!(from_str::<Vmf>(&written).unwrap(), map);
+        let world = crate::to_value(&map.world).unwrap();
+        assert_eq!(world.as_group().unwrap().get_all("solid").count(), map.world.solids.len());
+        assert_eq!(crate::from_value::<World>(world).unwrap(), map.world);
+
+        assert!("(0 0 0) (1 1 1)".parse::<Plane>().is_err());
+        assert!("(0 0 0) (1 1 1) (2 2 2) x".parse::<Plane>().is_err());
+        assert!(from_str::<Vmf>("entity { \"id\" \"2\" \"classname\" \"light\" }").is_err());
+    }
+}
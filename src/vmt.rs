@@ -0,0 +1,334 @@
+//! Materials, from the `.vmt` files that tell the Source engine how to draw a surface
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::error::{Error, Result};
+use crate::map::Map;
+use crate::ser::DOCUMENT;
+use crate::value::{diff, Document, Patch, Value};
+
+/// A material, which is a shader and the parameters it's drawn with
+///
+/// A `.vmt` file is a group named after the shader, with `$` parameters for the shader, `%` flags for
+/// the map compiler, and a `Proxies` group of proxies that change parameters while the game runs.
+/// Anything else in it, like fallback groups for other shaders, is kept with the parameters, so
+/// reading a material and writing it back doesn't lose anything. Names are matched regardless of case,
+/// like the engine does.
+///
+/// A material remembers the text it was read from, so writing it back keeps everything in the order
+/// it was in, and `Display` also keeps the comments and layout of whatever hasn't changed. Anything
+/// new goes at the end. Materials can also be read and written with [`from_str`](../fn.from_str.html)
+/// and [`to_string`](../fn.to_string.html), which keep the order but not the comments.
+///
+/// ```
+/// use vdf_serde::vmt::Material;
+///
+/// let mut material: Material = r#"LightmappedGeneric
+/// {
+///     $basetexture "concrete/concretefloor001a"
+///     %keywords "portal"
+///     Proxies
+///     {
+///         Sine { sinemin 0 sinemax 1 sineperiod 2 resultVar $alpha }
+///     }
+/// }"#.parse()?;
+/// assert_eq!(material.shader, "LightmappedGeneric");
+/// assert_eq!(material.param("$BaseTexture"), Some("concrete/concretefloor001a"));
+/// assert_eq!(material.flag("%keywords"), Some("portal"));
+/// assert_eq!(material.proxies[0].name, "Sine");
+///
+/// material.set_param("$basetexture", "concrete/concretefloor002a");
+/// assert!(material.to_string().starts_with("LightmappedGeneric\n{\n    $basetexture \"concrete/concretefloor002a\"\n    %keywords"));
+/// assert!(vdf_serde::to_string(&material)?.starts_with("\"LightmappedGeneric\"\n{\n\t\"$basetexture\""));
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Material {
+    /// The shader, like `"LightmappedGeneric"` or `"VertexLitGeneric"`
+    pub shader: String,
+    /// The `$` parameters, along with anything else that isn't a flag or the proxies, in order
+    pub params: Map<String, Value>,
+    /// The `%` flags for the map compiler, like `%compilenodraw` or `%keywords`, in order
+    pub flags: Map<String, String>,
+    /// The proxies, in order
+    pub proxies: Vec<Proxy>,
+    /// What the material was read from, which says where everything goes when it's written back
+    source: Option<Document>,
+}
+
+/// A proxy, which changes some of a material's parameters while the game runs
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Proxy {
+    /// Which proxy it is, like `"Sine"` or `"AnimatedTexture"`
+    pub name: String,
+    /// Its settings, like `"resultVar"`, which are often the names of the material's parameters
+    pub params: Map<String, String>,
+}
+
+impl Material {
+    /// Creates a material for a shader, with no parameters yet
+    pub fn new(shader: impl Into<String>) -> Self {
+        Material {
+            shader: shader.into(),
+            ..Material::default()
+        }
+    }
+
+    /// Returns a parameter's value, if it's there and it's a string
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).and_then(|(_, value)| value.as_str())
+    }
+
+    /// Sets a parameter, replacing it if it's already there and adding it after the others if it isn't
+    pub fn set_param(&mut self, name: impl Into<String>, value: impl Into<Value>) {
+        let name = name.into();
+        match self.params.iter_mut().find(|(key, _)| key.eq_ignore_ascii_case(&name)) {
+            Some((_, old)) => *old = value.into(),
+            None => self.params.append(name, value.into()),
+        }
+    }
+
+    /// Removes a parameter, and returns whatever it was set to
+    pub fn remove_param(&mut self, name: &str) -> Vec<Value> {
+        let mut removed = Vec::new();
+        let params = std::mem::take(&mut self.params);
+        for (key, value) in params {
+            if key.eq_ignore_ascii_case(name) {
+                removed.push(value);
+            } else {
+                self.params.append(key, value);
+            }
+        }
+        removed
+    }
+
+    /// Returns a flag's value, if it's there
+    pub fn flag(&self, name: &str) -> Option<&str> {
+        self.flags.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+
+    /// Puts the material back together as a document, with everything that was in the source where it was
+    fn to_value(&self) -> Value {
+        let source = match self.source.as_ref().map(Document::value) {
+            Some(Value::Group(document)) => document.get(&self.shader).and_then(Value::as_group),
+            _ => None,
+        };
+        let mut params = self.params.iter().map(Some).collect::<Vec<_>>();
+        let mut flags = self.flags.iter().map(Some).collect::<Vec<_>>();
+        let mut proxies_key = None;
+        let mut body = Map::new();
+        for (key, value) in source.into_iter().flatten() {
+            if key.eq_ignore_ascii_case("Proxies") && value.is_group() {
+                if proxies_key.is_none() && !self.proxies.is_empty() {
+                    body.append(key.clone(), self.proxies_value());
+                }
+                proxies_key = Some(key.clone());
+                continue;
+            }
+            // each entry in the source takes the next one with the same key, if there still is one
+            let found = if key.starts_with('%') && value.is_str() {
+                take(&mut flags, key).map(|flag| Value::String(flag.clone()))
+            } else {
+                take(&mut params, key).cloned()
+            };
+            if let Some(found) = found {
+                body.append(key.clone(), found);
+            }
+        }
+        body.extend(params.into_iter().flatten().map(|(key, value)| (key.clone(), value.clone())));
+        body.extend(flags.into_iter().flatten().map(|(key, flag)| (key.clone(), Value::String(flag.clone()))));
+        if proxies_key.is_none() && !self.proxies.is_empty() {
+            body.append("Proxies".to_string(), self.proxies_value());
+        }
+        let mut document = Map::new();
+        document.append(self.shader.clone(), Value::Group(body));
+        Value::Group(document)
+    }
+
+    fn proxies_value(&self) -> Value {
+        Value::Group(
+            self.proxies
+                .iter()
+                .map(|proxy| (proxy.name.clone(), Value::Group(proxy.params.iter().map(|(key, text)| (key.clone(), Value::String(text.clone()))).collect())))
+                .collect(),
+        )
+    }
+}
+
+/// Takes the first entry that hasn't been taken yet with the key `key`
+fn take<'a, T>(entries: &mut [Option<(&String, &'a T)>], key: &str) -> Option<&'a T> {
+    let slot = entries.iter_mut().find(|entry| matches!(entry, Some((other, _)) if *other == key))?;
+    slot.take().map(|(_, value)| value)
+}
+
+/// Materials are the same if they say the same thing, whether or not they came from the same text
+impl PartialEq for Material {
+    fn eq(&self, other: &Self) -> bool {
+        self.shader == other.shader && self.params == other.params && self.flags == other.flags && self.proxies == other.proxies
+    }
+}
+
+impl Eq for Material {}
+
+impl Hash for Material {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.shader.hash(state);
+        self.params.hash(state);
+        self.flags.hash(state);
+        self.proxies.hash(state);
+    }
+}
+
+impl TryFrom<Value> for Material {
+    type Error = Error;
+
+    /// Reads a material from a document, which has to be a single group named after the shader
+    fn try_from(value: Value) -> Result<Self> {
+        Material::try_from(Document::from(value))
+    }
+}
+
+impl TryFrom<Document> for Material {
+    type Error = Error;
+
+    /// Reads a material from a document, which has to be a single group named after the shader
+    fn try_from(document: Document) -> Result<Self> {
+        let mut entries = match document.value().clone() {
+            Value::Group(entries) => entries.into_iter(),
+            Value::String(_) => return Err(Error::Expected("a shader group", "string".to_string())),
+        };
+        let (shader, body) = match (entries.next(), entries.next()) {
+            (Some((shader, Value::Group(body))), None) => (shader, body),
+            (Some((shader, _)), None) => return Err(Error::Expected("a shader group", format!("a string for {:?}", shader))),
+            (None, _) => return Err(Error::Expected("a shader group", "nothing".to_string())),
+            (Some(_), Some((key, _))) => return Err(Error::Expected("a single shader group", format!("another entry {:?}", key))),
+        };
+
+        let mut material = Material::new(shader);
+        for (key, value) in body {
+            match value {
+                Value::Group(proxies) if key.eq_ignore_ascii_case("Proxies") => {
+                    for (name, params) in proxies {
+                        material.proxies.push(Proxy::try_from((name, params))?);
+                    }
+                }
+                Value::String(flag) if key.starts_with('%') => material.flags.append(key, flag),
+                value => material.params.append(key, value),
+            }
+        }
+        material.source = Some(document);
+        Ok(material)
+    }
+}
+
+impl TryFrom<(String, Value)> for Proxy {
+    type Error = Error;
+
+    fn try_from((name, params): (String, Value)) -> Result<Self> {
+        let params = match params {
+            Value::Group(params) => params,
+            Value::String(_) => return Err(Error::Expected("a group", format!("a string for proxy {:?}", name))),
+        };
+        let mut proxy = Proxy { name, params: Map::with_capacity(params.len()) };
+        for (key, value) in params {
+            match value {
+                Value::String(text) => proxy.params.append(key, text),
+                Value::Group(_) => return Err(Error::Expected("a string", format!("a group for {:?} in proxy {:?}", key, proxy.name))),
+            }
+        }
+        Ok(proxy)
+    }
+}
+
+impl From<Material> for Value {
+    fn from(material: Material) -> Self {
+        material.to_value()
+    }
+}
+
+impl FromStr for Material {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Material::try_from(s.parse::<Document>()?)
+    }
+}
+
+impl fmt::Display for Material {
+    /// Writes the material over the text it was read from, so only what's changed is written differently
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.to_value();
+        if let Some(source) = &self.source {
+            let mut document = source.clone();
+            if Patch::from(&diff(source.value(), &value)).apply_to_document(&mut document).is_ok() {
+                return formatter.write_str(document.as_str());
+            }
+        }
+        value.fmt(formatter)
+    }
+}
+
+impl Serialize for Material {
+    /// Writes the material as a group named after the shader
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(DOCUMENT, &self.to_value())
+    }
+}
+
+impl<'de> Deserialize<'de> for Material {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        Material::try_from(Value::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_material() {
+        let text = concat!(
+            "// a water material\n",
+            "\"Water\"\n{\n",
+            "\t\"$normalmap\"\t\"liquids/water_normal\"\n",
+            "\t\"%compilewater\"\t\"1\"\n",
+            "\t\">=dx90\"\n\t{\n\t\t\"$fallbackmaterial\"\t\"liquids/water_dx80\"\n\t}\n",
+            "\t\"proxies\"\n\t{\n",
+            "\t\t\"AnimatedTexture\"\n\t\t{\n\t\t\t\"animatedtexturevar\"\t\"$normalmap\"\n\t\t}\n",
+            "\t\t\"TextureScroll\"\n\t\t{\n\t\t\t\"texturescrollvar\"\t\"$bumptransform\"\n\t\t}\n",
+            "\t}\n",
+            "\t\"$unknown_param\"\t\"kept\"\n",
+            "}"
+        );
+        let mut material: Material = text.parse().unwrap();
+        assert_eq!(material.params.keys().collect::<Vec<_>>(), ["$normalmap", ">=dx90", "$unknown_param"]);
+        assert_eq!(material.flag("%COMPILEWATER"), Some("1"));
+        assert_eq!(material.proxies.iter().map(|proxy| proxy.name.as_str()).collect::<Vec<_>>(), ["AnimatedTexture", "TextureScroll"]);
+        assert_eq!(material.param(">=dx90"), None);
+
+        // nothing's changed, so it's written back just as it was
+        assert_eq!(material.to_string(), text);
+        let serialized = crate::to_string(&material).unwrap();
+        assert_eq!(serialized, text.trim_start_matches("// a water material\n"));
+        assert_eq!(crate::from_str::<Material>(&serialized).unwrap(), material);
+        assert_eq!(text.parse::<Material>().unwrap(), material);
+        assert_eq!(crate::from_str::<Material>(text).unwrap(), material);
+
+        assert_eq!(material.remove_param("$NormalMap"), [Value::from("liquids/water_normal")]);
+        material.set_param("$UNKNOWN_PARAM", "changed");
+        assert_eq!(material.param("$unknown_param"), Some("changed"));
+        material.flags.append("%keywords".to_string(), "water".to_string());
+        let written = material.to_string();
+        assert!(written.starts_with("// a water material\n\"Water\"\n{\n\t\"%compilewater\"\t\"1\"\n\t\">=dx90\""));
+        assert!(written.ends_with("\t\"$unknown_param\"\t\"changed\"\n\t\"%keywords\"\t\"water\"\n}"));
+        assert_eq!(written.parse::<Material>().unwrap(), material);
+
+        assert!("\"a\" { \"b\" \"c\" } \"d\" { }".parse::<Material>().is_err());
+        assert!("\"a\" { \"proxies\" { \"Sine\" { \"x\" { } } } }".parse::<Material>().is_err());
+    }
+}
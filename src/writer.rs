@@ -0,0 +1,233 @@
+//! Write VDF one piece at a time, without going through Serde
+
+use std::io;
+
+use crate::error::{Error, Result};
+
+/// A streaming VDF writer, which handles quoting, escaping, and indentation
+///
+/// Each key-value pair, group key, brace, and comment goes on its own line, indented with one
+/// tab per level of nesting. Nothing is buffered beyond what `W` does itself, so wrapping a file
+/// in a `BufWriter` is a good idea.
+///
+/// ```
+/// use vdf_serde::Writer;
+///
+/// let mut writer = Writer::new(Vec::new());
+/// writer.begin_group("AppState")?;
+/// writer.comment("written by hand")?;
+/// writer.write_pair("appid", "620")?;
+/// writer.begin_group("UserConfig")?;
+/// writer.write_pair("language", "english")?;
+/// writer.end_group()?;
+/// writer.end_group()?;
+/// assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), "\"AppState\"
+/// {
+/// \t// written by hand
+/// \t\"appid\"\t\"620\"
+/// \t\"UserConfig\"
+/// \t{
+/// \t\t\"language\"\t\"english\"
+/// \t}
+/// }");
+/// # Ok::<(), vdf_serde::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Writer<W: io::Write> {
+    inner: W,
+    depth: usize,
+    started: bool,
+}
+
+impl<W: io::Write> Writer<W> {
+    /// Creates a writer that writes into `inner`
+    pub fn new(inner: W) -> Self {
+        Writer {
+            inner,
+            depth: 0,
+            started: false,
+        }
+    }
+
+    /// Returns how many groups are currently open
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer
+    ///
+    /// Writing to it directly will probably mess up the output.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Gives back the underlying writer
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Writes `"key"` and opens a group under it
+    pub fn begin_group(&mut self, key: &str) -> Result<()> {
+        self.start_line()?;
+        self.write_quoted(key)?;
+        self.open_group()
+    }
+
+    /// Writes `"key" [condition]` and opens a group under it, for a group that only applies when
+    /// `condition` (like `$X360`, without brackets) holds
+    pub fn begin_conditional_group(&mut self, key: &str, condition: &str) -> Result<()> {
+        self.start_line()?;
+        self.write_quoted(key)?;
+        self.write_condition(condition)?;
+        self.open_group()
+    }
+
+    /// Opens a group with no key in front of it, which only really makes sense at the top level
+    pub fn begin_unnamed_group(&mut self) -> Result<()> {
+        self.open_group()
+    }
+
+    /// Closes the innermost open group
+    ///
+    /// # Errors
+    ///
+    /// If no group is open, an error will be returned.
+    pub fn end_group(&mut self) -> Result<()> {
+        if self.depth == 0 {
+            return Err(Error::Message("no group to end".to_string()));
+        }
+        self.depth -= 1;
+        self.start_line()?;
+        self.write_raw("}")
+    }
+
+    /// Writes `"key"` and `"value"` on one line, separated by a tab
+    pub fn write_pair(&mut self, key: &str, value: &str) -> Result<()> {
+        self.start_line()?;
+        self.write_quoted(key)?;
+        self.write_raw("\t")?;
+        self.write_quoted(value)
+    }
+
+    /// Writes `"key"`, `"value"`, and `[condition]` on one line, for a pair that only applies when
+    /// `condition` (like `$X360`, without brackets) holds
+    pub fn write_conditional_pair(&mut self, key: &str, value: &str, condition: &str) -> Result<()> {
+        self.write_pair(key, value)?;
+        self.write_condition(condition)
+    }
+
+    /// Writes `"value"` on its own line with no key, which only really makes sense as the whole document
+    pub fn write_value(&mut self, value: &str) -> Result<()> {
+        self.start_line()?;
+        self.write_quoted(value)
+    }
+
+    /// Writes a `//` comment, with one line of output for each line of `text`
+    pub fn comment(&mut self, text: &str) -> Result<()> {
+        for line in text.lines() {
+            self.start_line()?;
+            self.write_raw("// ")?;
+            self.write_raw(line)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a `//` comment at the end of the last line, or on its own line if there isn't a last line
+    /// or `text` has more than one line
+    pub fn trailing_comment(&mut self, text: &str) -> Result<()> {
+        if !self.started || text.contains('\n') {
+            return self.comment(text);
+        }
+        self.write_raw("\t// ")?;
+        self.write_raw(text)
+    }
+
+    /// Writes a directive like `#base "file.vdf"` on its own line
+    pub fn directive(&mut self, name: &str, value: &str) -> Result<()> {
+        self.start_line()?;
+        self.write_raw("#")?;
+        self.write_raw(name)?;
+        self.write_raw(" ")?;
+        self.write_quoted(value)
+    }
+
+    /// Flushes the underlying writer
+    pub fn flush(&mut self) -> Result<()> {
+        self.inner.flush().map_err(|err| Error::Io(err.to_string()))
+    }
+
+    fn open_group(&mut self) -> Result<()> {
+        self.start_line()?;
+        self.write_raw("{")?;
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Lines are separated rather than terminated, so the output doesn't end with a newline
+    fn start_line(&mut self) -> Result<()> {
+        if self.started {
+            self.write_raw("\n")?;
+        }
+        self.started = true;
+        for _ in 0..self.depth {
+            self.write_raw("\t")?;
+        }
+        Ok(())
+    }
+
+    fn write_condition(&mut self, condition: &str) -> Result<()> {
+        self.write_raw(" [")?;
+        self.write_raw(condition)?;
+        self.write_raw("]")
+    }
+
+    fn write_quoted(&mut self, text: &str) -> Result<()> {
+        #[cfg(feature = "escape")]
+        let escaped = text
+            .replace('\\', r"\\")
+            .replace('\n', r"\n")
+            .replace('\t', r"\t")
+            .replace('"', r#"\""#);
+        #[cfg(feature = "escape")]
+        let text = escaped.as_str();
+
+        self.write_raw("\"")?;
+        self.write_raw(text)?;
+        self.write_raw("\"")
+    }
+
+    fn write_raw(&mut self, text: &str) -> Result<()> {
+        self.inner.write_all(text.as_bytes()).map_err(|err| Error::Io(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writer() {
+        let mut writer = Writer::new(Vec::new());
+        writer.trailing_comment("two\nlines").unwrap();
+        writer.directive("base", "base.vdf").unwrap();
+        writer.begin_unnamed_group().unwrap();
+        writer.write_pair("quote\"d", "").unwrap();
+        writer.trailing_comment("empty").unwrap();
+        writer.write_conditional_pair("a", "b", "$X360").unwrap();
+        writer.begin_conditional_group("c", "!$X360").unwrap();
+        writer.end_group().unwrap();
+        writer.end_group().unwrap();
+        assert_eq!(writer.end_group(), Err(Error::Message("no group to end".to_string())));
+        let expected = if cfg!(feature = "escape") {
+            "// two\n// lines\n#base \"base.vdf\"\n{\n\t\"quote\\\"d\"\t\"\"\t// empty\n\t\"a\"\t\"b\" [$X360]\n\t\"c\" [!$X360]\n\t{\n\t}\n}"
+        } else {
+            "// two\n// lines\n#base \"base.vdf\"\n{\n\t\"quote\"d\"\t\"\"\t// empty\n\t\"a\"\t\"b\" [$X360]\n\t\"c\" [!$X360]\n\t{\n\t}\n}"
+        };
+        assert_eq!(String::from_utf8(writer.into_inner()).unwrap(), expected);
+    }
+}
@@ -0,0 +1,139 @@
+#![cfg(feature = "cli")]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn vdf(args: &[&str], stdin: impl AsRef<[u8]>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_vdf"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_ref()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+const MANIFEST: &str = r#""AppState" { "appid" "620" "InstalledDepots" { "621" { "manifest" "1" } "622" { "manifest" "2" } } }"#;
+
+#[test]
+fn test_fmt_and_check() {
+    let output = vdf(&["fmt"], "\"a\" { \"b\" \"c\" }");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "\"a\"\n{\n\t\"b\"\t\"c\"\n}\n");
+    assert_eq!(vdf(&["fmt", "--check"], "\"a\" \"b\"").status.code(), Some(1));
+    assert_eq!(vdf(&["fmt", "--check"], "\"a\"\t\"b\"\n").status.code(), Some(0));
+
+    // comments and directives are kept
+    let commented = "// top\n#base \"base.vdf\"\n\"a\" { // opens\n  \"b\" \"c\" // trailing\n  // own line\n}";
+    let output = vdf(&["fmt"], commented);
+    assert_eq!(stdout(&output), "// top\n#base \"base.vdf\"\n\"a\"\n{\t// opens\n\t\"b\"\t\"c\"\t// trailing\n\t// own line\n}\n");
+    assert_eq!(vdf(&["fmt", "--check"], stdout(&output)).status.code(), Some(0));
+
+    // and so are conditions
+    let output = vdf(&["fmt"], "\"a\" [$X360] { \"b\" \"c\" [!$X360] // trailing\n\"d\" \"e\" }");
+    assert_eq!(stdout(&output), "\"a\" [$X360]\n{\n\t\"b\"\t\"c\" [!$X360]\t// trailing\n\t\"d\"\t\"e\"\n}\n");
+    assert_eq!(vdf(&["fmt", "--check"], stdout(&output)).status.code(), Some(0));
+
+    let output = vdf(&["check"], "\"a\"\n{\n\t\"b\"\n}");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).starts_with("-:4:1: "));
+}
+
+#[test]
+fn test_get_and_set() {
+    let output = vdf(&["get", "--paths", "-i", "appstate/installeddepots/*/manifest"], MANIFEST);
+    assert_eq!(stdout(&output), "AppState/InstalledDepots/621/manifest\t1\nAppState/InstalledDepots/622/manifest\t2\n");
+    assert_eq!(vdf(&["get", "AppState/nope"], MANIFEST).status.code(), Some(1));
+    let output = vdf(&["get", "AppState/InstalledDepots/621"], MANIFEST);
+    assert_eq!(stdout(&output), "{\n\t\"manifest\"\t\"1\"\n}\n");
+
+    // set leaves everything it doesn't change alone
+    let output = vdf(&["set", "AppState/InstalledDepots/*/manifest", "3"], MANIFEST);
+    assert_eq!(stdout(&output), MANIFEST.replace("\"1\"", "\"3\"").replace("\"2\"", "\"3\""));
+    let output = vdf(&["set", "AppState/buildid", "4"], MANIFEST);
+    assert_eq!(stdout(&output), MANIFEST.replace("} } }", "} } \"buildid\"\t\"4\" }"));
+    let commented = "\"a\"\n{\n    // keep me\n    \"b\"    \"1\"\n}\n";
+    assert_eq!(stdout(&vdf(&["set", "a/b", "2"], commented)), commented.replace("\"1\"", "\"2\""));
+
+    // and adds any groups on the way to a new value
+    let output = vdf(&["set", "a/x/y", "2"], commented);
+    assert_eq!(stdout(&output), commented.replace("}\n", "    \"x\"\n    {\n        \"y\"\t\"2\"\n    }\n}\n"));
+    let output = vdf(&["set", "-i", "A/x/y", "2"], commented);
+    assert_eq!(stdout(&output), stdout(&vdf(&["set", "a/x/y", "2"], commented)));
+    assert_eq!(vdf(&["set", "a/b/c", "2"], commented).status.code(), Some(2));
+}
+
+#[test]
+fn test_set_ignoring_case() {
+    // keys that only differ in case are separate keys to a patch, so the paths set uses have to count them separately
+    let input = "\"R\"\n{\n\t\"dup\"\t\"0\"\n\t\"Dup\"\t\"1\"\n\t\"dup\"\t\"2\"\n}\n";
+    let output = vdf(&["get", "-i", "--paths", "r/DUP"], input);
+    assert_eq!(stdout(&output), "R/dup[0]\t0\nR/Dup\t1\nR/dup[1]\t2\n");
+    let output = vdf(&["set", "-i", "r/DUP", "x"], input);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(stdout(&output), input.replace("\"0\"", "\"x\"").replace("\"1\"", "\"x\"").replace("\"2\"", "\"x\""));
+}
+
+#[test]
+fn test_write_keeps_comments() {
+    let dir = std::env::temp_dir().join(format!("vdf-cli-write-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("config.vdf");
+    let path = file.to_str().unwrap();
+
+    std::fs::write(&file, "// settings\n\"a\" { \"b\" \"1\" } // done\n").unwrap();
+    assert!(vdf(&["fmt", "--write", path], "").status.success());
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "// settings\n\"a\"\n{\n\t\"b\"\t\"1\"\n}\t// done\n");
+    assert!(vdf(&["set", "--write", "a/b", "2", path], "").status.success());
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "// settings\n\"a\"\n{\n\t\"b\"\t\"2\"\n}\t// done\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_utf16() {
+    let utf16 = |text: &str| vdf_serde::Encoding::Utf16Le.encode(text).unwrap();
+    assert_eq!(vdf(&["check"], utf16("\"a\" \"b\"")).status.code(), Some(0));
+    assert_eq!(stdout(&vdf(&["get", "a"], b"\"\0a\0\"\0 \0\"\0b\0\"\0")), "b\n");
+
+    // files are written back in the encoding they were read in
+    let dir = std::env::temp_dir().join(format!("vdf-cli-utf16-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("mod_english.txt");
+    let path = file.to_str().unwrap();
+    std::fs::write(&file, utf16("\"lang\" { \"Tokens\" { \"Hello\" \"Hall\u{f6}\" } }")).unwrap();
+    assert!(vdf(&["fmt", "--write", path], "").status.success());
+    assert_eq!(std::fs::read(&file).unwrap(), utf16("\"lang\"\n{\n\t\"Tokens\"\n\t{\n\t\t\"Hello\"\t\"Hall\u{f6}\"\n\t}\n}\n"));
+    assert!(vdf(&["set", "--write", "lang/Tokens/Bye", "Tsch\u{fc}ss", path], "").status.success());
+    assert_eq!(std::fs::read(&file).unwrap(), utf16("\"lang\"\n{\n\t\"Tokens\"\n\t{\n\t\t\"Hello\"\t\"Hall\u{f6}\"\n\t\t\"Bye\"\t\"Tsch\u{fc}ss\"\n\t}\n}\n"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_json_and_diff() {
+    let json = vdf(&["to-json"], MANIFEST);
+    assert!(json.status.success());
+    let back = vdf(&["from-json"], stdout(&json));
+    assert_eq!(stdout(&back), stdout(&vdf(&["fmt"], MANIFEST)));
+
+    let conditional = "\"a\"\t\"1\" [$X360]\n\"a\"\t\"2\" [!$X360]\n";
+    let back = vdf(&["from-json"], stdout(&vdf(&["to-json"], conditional)));
+    assert_eq!(stdout(&back), conditional);
+
+    let dir = std::env::temp_dir().join(format!("vdf-cli-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let old = dir.join("old.vdf");
+    let new = dir.join("new.vdf");
+    std::fs::write(&old, "\"a\" \"1\"").unwrap();
+    std::fs::write(&new, "\"a\" \"2\"").unwrap();
+    let output = vdf(&["diff", old.to_str().unwrap(), new.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output), "@@ a @@\n-\"1\"\n+\"2\"\n");
+    assert_eq!(vdf(&["diff", "-", "-"], "\"a\" \"1\"").status.code(), Some(2));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,17 @@
+use vdf_serde::Localization;
+
+const PORTAL2_FRENCH: &[u8] = include_bytes!("fixtures/localization/portal2_french.txt");
+
+#[test]
+fn test_conditions() {
+    let localization = Localization::from_bytes(PORTAL2_FRENCH).unwrap();
+    assert_eq!(localization.language, "french");
+    assert_eq!(
+        localization.tokens.keys().collect::<Vec<_>>(),
+        ["Portal_Title", "Menu_Quit", "Menu_Quit", "Menu_Options", "Hint_Jump", "Hint_Jump", "Menu_Credits"]
+    );
+    assert_eq!(localization.get("menu_quit"), Some("Quitter"));
+    assert_eq!(localization.get("Menu_Options"), Some("Options"));
+    assert_eq!(localization.get("Menu_Credits"), Some("G\u{e9}n\u{e9}rique"));
+    assert_eq!(localization.original("Menu_Quit"), Some("Quit"));
+}
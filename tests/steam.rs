@@ -0,0 +1,122 @@
+#![cfg(feature = "steam")]
+
+use vdf_serde::steam::{self, AppManifest, Config, Library, LibraryFolder, LibraryFolders, LocalConfig, LoginUsers};
+use vdf_serde::Error;
+
+const LIBRARY_FOLDERS: &str = include_str!("fixtures/steam/libraryfolders.vdf");
+const LIBRARY_FOLDERS_OLD: &str = include_str!("fixtures/steam/libraryfolders_old.vdf");
+const APP_MANIFEST: &str = include_str!("fixtures/steam/appmanifest_620.acf");
+const LOGIN_USERS: &str = include_str!("fixtures/steam/loginusers.vdf");
+const CONFIG: &str = include_str!("fixtures/steam/config.vdf");
+const LOCAL_CONFIG: &str = include_str!("fixtures/steam/localconfig.vdf");
+
+#[test]
+fn test_library_folders() {
+    let folders: LibraryFolders = steam::from_str(LIBRARY_FOLDERS).unwrap();
+    assert_eq!(folders.0.len(), 2);
+    assert_eq!(folders.0[&0].path, r"C:\Program Files (x86)\Steam");
+    assert_eq!(folders.0[&1].label, "Games");
+    assert_eq!(folders.0[&1].total_size, Some(1_000_186_310_656));
+    assert!(folders.0[&0].contains(620) && !folders.0[&0].contains(440));
+    assert_eq!(steam::from_str::<LibraryFolders>(&vdf_serde::to_string(&folders).unwrap()).unwrap(), folders);
+
+    let old: LibraryFolders = steam::from_str(LIBRARY_FOLDERS_OLD).unwrap();
+    assert_eq!(old.0.keys().collect::<Vec<_>>(), [&1, &2]);
+    assert_eq!(old.0[&2], LibraryFolder { path: r"E:\Games\Steam".to_string(), ..LibraryFolder::default() });
+    assert!(vdf_serde::to_string(&old).unwrap().starts_with("\"libraryfolders\"\n{\n\t\"1\"\n\t{\n\t\t\"path\"\t\"D:"));
+}
+
+#[test]
+fn test_app_manifest() {
+    let app: AppManifest = steam::from_str(APP_MANIFEST).unwrap();
+    assert_eq!((app.app_id, app.name.as_str(), app.install_dir.as_str()), (620, "Portal 2", "Portal 2"));
+    assert!(app.is_fully_installed());
+    assert_eq!(app.build_id, 12_034_567);
+    assert_eq!(app.last_owner, 76_561_198_000_000_001);
+    assert_eq!(app.installed_depots[&659].dlc_app_id, Some(644));
+    assert_eq!(app.installed_depots[&621].manifest, 7_180_225_386_553_412_365);
+    assert_eq!(app.shared_depots[&228_988], 228_980);
+    assert_eq!(app.user_config["language"], "english");
+
+    let written = vdf_serde::to_string(&app).unwrap();
+    assert!(written.starts_with("\"AppState\"\n{\n\t\"appid\"\t\"620\"\n\t\"Universe\"\t\"1\""));
+    assert_eq!(steam::from_str::<AppManifest>(&written).unwrap(), app);
+    // the root name and keys can be in any case
+    let lowercase = steam::from_str::<AppManifest>(&APP_MANIFEST.to_lowercase()).unwrap();
+    assert_eq!(lowercase.build_id, app.build_id);
+    assert!(vdf_serde::from_str::<AppManifest>(&APP_MANIFEST.to_lowercase()).is_err());
+}
+
+#[test]
+fn test_login_users() {
+    let users: LoginUsers = steam::from_str(LOGIN_USERS).unwrap();
+    let (id, user) = users.most_recent().unwrap();
+    assert_eq!((id, user.account_name.as_str()), (76_561_198_000_000_001, "chell"));
+    assert!(user.remember_password && user.allow_auto_login && !user.wants_offline_mode);
+    let other = &users.0[&76_561_198_000_000_002];
+    assert!(!other.most_recent && !other.allow_auto_login);
+    assert_eq!(steam::from_str::<LoginUsers>(&vdf_serde::to_string(&users).unwrap()).unwrap(), users);
+}
+
+#[test]
+fn test_configs() {
+    let config: Config = steam::from_str(CONFIG).unwrap();
+    assert_eq!(config.steam().accounts["chell"].steam_id, 76_561_198_000_000_001);
+    let proton = &config.steam().compat_tool_mapping[&620];
+    assert_eq!((proton.name.as_str(), proton.config.as_str(), proton.priority), ("proton_8", "noesync", 250));
+    assert_eq!(steam::from_str::<Config>(&vdf_serde::to_string(&config).unwrap()).unwrap(), config);
+
+    let local: LocalConfig = steam::from_str(LOCAL_CONFIG).unwrap();
+    let portal = &local.steam().apps[&620];
+    assert_eq!((portal.last_played, portal.playtime), (Some(1_700_086_400), Some(1234)));
+    assert_eq!(portal.launch_options.as_deref(), Some("-novid +mat_motion_blur_enabled 0"));
+    assert_eq!(local.steam().apps[&440].launch_options, None);
+    assert_eq!(steam::from_str::<LocalConfig>(&vdf_serde::to_string(&local).unwrap()).unwrap(), local);
+}
+
+#[test]
+fn test_library() {
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/steam_root/Steam");
+    let library = Library::open(&root).unwrap();
+    assert_eq!(library.root(), root);
+    let folders = library.folders();
+    assert_eq!(folders.len(), 3);
+    assert_eq!(folders[0].path, root);
+    assert_eq!(folders[1].info.as_ref().unwrap().label, "Games");
+    assert!(folders[1].is_available() && !folders[2].is_available());
+
+    let apps = library.apps().unwrap();
+    let ids = apps.iter().map(|app| app.manifest.app_id).collect::<Vec<_>>();
+    assert_eq!(ids, [620, 70, 440]);
+    assert_eq!(apps[0].install_path, root.join("steamapps/common/Portal 2"));
+    assert!(apps[0].install_path.join("README.txt").is_file());
+    assert_eq!(apps[2].manifest.size_on_disk, 27_421_375_638);
+    assert!(!apps[1].manifest.is_fully_installed());
+
+    let tf2 = library.app(440).unwrap().unwrap();
+    assert_eq!(tf2.manifest_path, root.join("../../steam_library/steamapps/appmanifest_440.acf"));
+    assert_eq!(tf2.install_path, root.join("../../steam_library/steamapps/common/Team Fortress 2"));
+    assert_eq!(library.app(1).unwrap(), None);
+
+    let missing = Library::open(root.join("steamapps")).unwrap_err();
+    assert!(matches!(missing, Error::File { .. }));
+    assert!(folders[2].apps().is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_library_symlink() {
+    let dir = std::env::temp_dir().join(format!("vdf-steam-symlink-test-{}", std::process::id()));
+    let root = dir.join("Steam");
+    std::fs::create_dir_all(root.join("steamapps")).unwrap();
+    std::os::unix::fs::symlink(&root, dir.join("steam")).unwrap();
+    let list = format!("\"libraryfolders\"\n{{\n\t\"0\"\n\t{{\n\t\t\"path\"\t\t\"{}\"\n\t}}\n}}\n", dir.join("steam").display());
+    std::fs::write(root.join("steamapps/libraryfolders.vdf"), list).unwrap();
+
+    let library = Library::open(&root);
+    std::fs::remove_dir_all(&dir).unwrap();
+    let library = library.unwrap();
+    assert_eq!(library.folders().len(), 1);
+    assert_eq!(library.folders()[0].path, root);
+    assert!(library.folders()[0].info.is_some());
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "json")]
+
+use vdf_serde::{json, Deserializer, DuplicateKeys, Value};
+
+const MANIFEST: &str = r#"#base "common.vdf"
+"AppState"
+{
+    // comments don't make it through
+    "appid"     "620"
+    "name"      "Portal 2"
+    "UserConfig" { "language" "english" }
+    "InstalledDepots"
+    {
+        "621" { "manifest" "1" }
+        "621" { "manifest" "2" }
+    }
+}"#;
+
+fn transcode(de: &mut Deserializer) -> String {
+    let mut out = Vec::new();
+    serde_transcode::transcode(&mut *de, &mut serde_json::Serializer::new(&mut out)).unwrap();
+    de.end().unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_transcode_matches_json_module() {
+    let transcoded = transcode(&mut Deserializer::from_str(MANIFEST));
+    let value: Value = MANIFEST.parse().unwrap();
+    assert_eq!(transcoded, json::to_string(&value).unwrap());
+    assert_eq!(json::from_str(&transcoded).unwrap(), value);
+    assert!(transcoded.contains(r#""InstalledDepots":[{"621":{"manifest":"1"}},{"621":{"manifest":"2"}}]"#));
+
+    let deduplicated = transcode(&mut Deserializer::from_str(MANIFEST).duplicate_keys(DuplicateKeys::LastWins));
+    assert!(deduplicated.contains(r#""InstalledDepots":{"621":{"manifest":"2"}}"#));
+
+    assert_eq!(transcode(&mut Deserializer::from_str(r#""lone""#)), r#""lone""#);
+    assert_eq!(transcode(&mut Deserializer::from_str(r#"{ "a" "b" }"#)), r#"{"a":"b"}"#);
+}